@@ -0,0 +1,249 @@
+package ch.dbrgn.candidateparser;
+
+import org.junit.Test;
+
+import java.net.Inet6Address;
+import java.net.InetAddress;
+import java.util.ArrayList;
+import java.util.List;
+
+import static org.junit.Assert.assertArrayEquals;
+import static org.junit.Assert.assertEquals;
+import static org.junit.Assert.assertFalse;
+import static org.junit.Assert.assertNull;
+import static org.junit.Assert.assertTrue;
+import static org.junit.Assert.fail;
+
+/**
+ * Tests for the JNI bindings.
+ *
+ * They run on Android (see CandidateParserTest) and in a desktop JVM (see
+ * the `jvm` integration test of the candidateparser-jni crate).
+ */
+public abstract class CandidateParserTestBase {
+    @Test
+    public void testCandidateParser() throws Exception {
+        final String sdpString = "candidate:842163049 1 udp 1686052607 1.2.3.4 46154 typ srflx raddr 10.0.0.17 rport 46154 generation 0 ufrag EEtu network-id 3 network-cost 10";
+        final IceCandidate response = CandidateParser.parseSdp(sdpString);
+        System.out.println("Parsed: " + response);
+        assertEquals("842163049", response.getFoundation());
+        assertEquals(1, response.getComponentId());
+        assertEquals("udp", response.getTransport());
+        assertEquals(1686052607L, response.getPriority());
+        assertEquals("1.2.3.4", response.getConnectionAddress());
+        assertEquals(46154, response.getPort());
+        assertEquals("srflx", response.getCandidateType());
+        assertEquals("10.0.0.17", response.getRelAddr());
+        assertEquals(Integer.valueOf(46154), response.getRelPort());
+        assertEquals(4, response.getExtensions().size());
+        assertEquals("0", response.getExtensions().get("generation"));
+        assertEquals("EEtu", response.getExtensions().get("ufrag"));
+        assertEquals("3", response.getExtensions().get("network-id"));
+        assertEquals("10", response.getExtensions().get("network-cost"));
+        assertArrayEquals("EEtu".getBytes(), response.getExtensionBytes("ufrag"));
+        assertNull(response.getExtensionBytes("foo"));
+        assertEquals(4, response.getExtensionList().size());
+        assertEquals("generation", response.getExtensionList().get(0).getKeyString());
+        assertEquals("ufrag", response.getExtensionList().get(3).getKeyString());
+    }
+
+    @Test
+    public void testParseSessionDescription() throws Exception {
+        final String sdp = "v=0\r\n" +
+                "m=audio 9 UDP/TLS/RTP/SAVPF 111\r\n" +
+                "a=mid:audio\r\n" +
+                "a=candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host\r\n" +
+                "m=video 9 UDP/TLS/RTP/SAVPF 96\r\n" +
+                "a=candidate:2 1 udp 1686052607 1.2.3.4 x typ srflx\r\n" +
+                "a=candidate:3 1 udp 1686052607 1.2.3.4 46154 typ srflx raddr 10.0.0.17 rport 46154\r\n";
+        final List<CandidateParseException> errors = new ArrayList<>();
+        final List<IceCandidate> candidates = CandidateParser.parseSessionDescription(sdp, errors);
+        assertEquals(2, candidates.size());
+
+        final IceCandidate first = candidates.get(0);
+        assertEquals("1", first.getFoundation());
+        assertEquals("audio", first.getSdpMid());
+        assertEquals(Integer.valueOf(0), first.getSdpMLineIndex());
+
+        // An invalid candidate does not prevent the following ones from being returned
+        final IceCandidate second = candidates.get(1);
+        assertEquals("3", second.getFoundation());
+        assertNull(second.getSdpMid());
+        assertEquals(Integer.valueOf(1), second.getSdpMLineIndex());
+
+        assertEquals(1, errors.size());
+        assertEquals("port", errors.get(0).getField());
+        assertEquals(Integer.valueOf(6), errors.get(0).getLine());
+
+        // Without an errors list, invalid candidates are skipped
+        assertEquals(2, CandidateParser.parseSessionDescription(sdp).size());
+    }
+
+    @Test
+    public void testToSdp() throws Exception {
+        final IceCandidate candidate = CandidateParser.parseSdp("candidate:842163049 1 udp 1686052607 1.2.3.4 46154 typ srflx raddr 10.0.0.17 rport 46154 ufrag EEtu generation 0");
+        assertEquals("candidate:842163049 1 udp 1686052607 1.2.3.4 46154 typ srflx raddr 10.0.0.17 rport 46154 generation 0 ufrag EEtu", candidate.toSdp());
+        candidate.setConnectionAddress("5.6.7.8");
+        candidate.addExtension("ufrag", "abcd");
+        assertEquals("candidate:842163049 1 udp 1686052607 5.6.7.8 46154 typ srflx raddr 10.0.0.17 rport 46154 generation 0 ufrag abcd", candidate.toSdp());
+
+        candidate.setPort(-1);
+        try {
+            candidate.toSdp();
+            fail("No exception thrown");
+        } catch (CandidateParseException e) {
+            assertEquals("Invalid port", e.getMessage());
+            assertEquals("port", e.getField());
+            assertNull(e.getOffset());
+        }
+        candidate.setPort(46154);
+
+        // A field cannot add further fields to the candidate
+        candidate.setCandidateType("srflx raddr 1.2.3.4");
+        try {
+            candidate.toSdp();
+            fail("No exception thrown");
+        } catch (CandidateParseException e) {
+            assertEquals("cand-type", e.getField());
+            assertNull(e.getOffset());
+        }
+        candidate.setCandidateType("srflx");
+        candidate.setRelPort(65536);
+        try {
+            candidate.toSdp();
+            fail("No exception thrown");
+        } catch (CandidateParseException e) {
+            assertEquals("rel-port", e.getField());
+        }
+
+        try {
+            new IceCandidate().toSdp();
+            fail("No exception thrown");
+        } catch (NullPointerException e) {
+            assertEquals("Missing foundation", e.getMessage());
+        }
+    }
+
+    @Test
+    public void testTypedFields() throws Exception {
+        final IceCandidate candidate = CandidateParser.parseSdp("candidate:842163049 1 udp 1686052607 1.2.3.4 46154 typ srflx raddr 2001:db8::1 rport 46154");
+        assertEquals(Transport.UDP, candidate.getTypedTransport());
+        assertEquals(CandidateType.SRFLX, candidate.getTypedCandidateType());
+        assertEquals(InetAddress.getByAddress(new byte[] {1, 2, 3, 4}), candidate.getTypedConnectionAddress());
+        assertEquals(InetAddress.getByName("2001:db8::1"), candidate.getTypedRelAddr());
+
+        candidate.setTypedCandidateType(CandidateType.HOST);
+        candidate.setTypedConnectionAddress(InetAddress.getByAddress(new byte[] {10, 0, 0, 1}));
+        candidate.setTypedRelAddr(null);
+        assertEquals("host", candidate.getCandidateType());
+        assertEquals("10.0.0.1", candidate.getConnectionAddress());
+        assertNull(candidate.getRelAddr());
+        assertNull(candidate.getTypedRelAddr());
+
+        candidate.setTransport("tcp");
+        candidate.setCandidateType("foo");
+        candidate.setConnectionAddress("1f2e3d4c-5b6a-7980-1234-56789abcdef0.local");
+        assertEquals(Transport.OTHER, candidate.getTypedTransport());
+        assertEquals(CandidateType.OTHER, candidate.getTypedCandidateType());
+        assertNull(candidate.getTypedConnectionAddress());
+        candidate.setConnectionAddress("1.2.3.256");
+        assertNull(candidate.getTypedConnectionAddress());
+
+        // Only literals are accepted, names are never looked up
+        candidate.setConnectionAddress("::ffff:10.0.0.1");
+        assertEquals(InetAddress.getByAddress(new byte[] {10, 0, 0, 1}), candidate.getTypedConnectionAddress());
+        candidate.setConnectionAddress("1:2:3:4:5:6:7::");
+        assertEquals(InetAddress.getByName("1:2:3:4:5:6:7:0"), candidate.getTypedConnectionAddress());
+        for (String address : new String[] {"x:1", "fe80::1%wlan0", "[::1]", "1::2::3", ":::1", "1:2:3:4:5:6:7:8:9", "1:2:3:4:5:6:7", "::12345", "1.2.3.4::", "::1.2.3"}) {
+            candidate.setConnectionAddress(address);
+            assertNull(address, candidate.getTypedConnectionAddress());
+        }
+    }
+
+    @Test
+    public void testTypedSetters() throws Exception {
+        final IceCandidate candidate = CandidateParser.parseSdp("candidate:1 1 tcp 2130706431 10.0.0.17 9 typ host");
+        assertEquals(Transport.OTHER, candidate.getTypedTransport());
+        candidate.setTypedTransport(Transport.UDP);
+        assertEquals("udp", candidate.getTransport());
+        assertEquals(Transport.UDP, candidate.getTypedTransport());
+        candidate.setTypedCandidateType(CandidateType.RELAY);
+        assertEquals("relay", candidate.getCandidateType());
+
+        try {
+            candidate.setTypedTransport(Transport.OTHER);
+            fail("No exception thrown");
+        } catch (IllegalArgumentException e) {
+            assertEquals("udp", candidate.getTransport());
+        }
+        try {
+            candidate.setTypedTransport(null);
+            fail("No exception thrown");
+        } catch (NullPointerException e) {
+            assertEquals("udp", candidate.getTransport());
+        }
+        try {
+            candidate.setTypedCandidateType(CandidateType.OTHER);
+            fail("No exception thrown");
+        } catch (IllegalArgumentException e) {
+            assertEquals("relay", candidate.getCandidateType());
+        }
+        try {
+            candidate.setTypedCandidateType(null);
+            fail("No exception thrown");
+        } catch (NullPointerException e) {
+            assertEquals("relay", candidate.getCandidateType());
+        }
+        assertEquals("candidate:1 1 udp 2130706431 10.0.0.17 9 typ relay", candidate.toSdp());
+
+        // The zone index of scoped IPv6 addresses cannot be serialized
+        final byte[] linkLocal = InetAddress.getByName("fe80::1").getAddress();
+        try {
+            candidate.setTypedConnectionAddress(Inet6Address.getByAddress(null, linkLocal, 1));
+            fail("No exception thrown");
+        } catch (IllegalArgumentException e) {
+            assertEquals("10.0.0.17", candidate.getConnectionAddress());
+        }
+        try {
+            candidate.setTypedRelAddr(Inet6Address.getByAddress(null, linkLocal, 1));
+            fail("No exception thrown");
+        } catch (IllegalArgumentException e) {
+            assertNull(candidate.getRelAddr());
+        }
+        candidate.setTypedConnectionAddress(InetAddress.getByAddress(linkLocal));
+        assertEquals("candidate:1 1 udp 2130706431 fe80::1 9 typ relay", candidate.toSdp());
+    }
+
+    @Test
+    public void testNullParameters() {
+        try {
+            CandidateParser.parseSdp(null);
+            fail("No exception thrown");
+        } catch (NullPointerException e) {
+            assertEquals("Missing sdp", e.getMessage());
+        } catch (CandidateParseException e) {
+            fail("Unexpected exception: " + e);
+        }
+        try {
+            CandidateParser.toSdp(null);
+            fail("No exception thrown");
+        } catch (NullPointerException e) {
+            assertEquals("Missing candidate", e.getMessage());
+        } catch (CandidateParseException e) {
+            fail("Unexpected exception: " + e);
+        }
+    }
+
+    @Test
+    public void testCandidateParserError() {
+        try {
+            CandidateParser.parseSdp("candidate:1 1 udp 1 1.2.3.4 65536 typ host");
+            fail("No exception thrown");
+        } catch (CandidateParseException e) {
+            assertEquals("Invalid port at offset 28", e.getMessage());
+            assertEquals("port", e.getField());
+            assertEquals(Integer.valueOf(28), e.getOffset());
+            assertNull(e.getLine());
+        }
+    }
+}
@@ -0,0 +1,55 @@
+package ch.dbrgn.candidateparser;
+
+/**
+ * Thrown if an ICE candidate could not be parsed.
+ */
+public class CandidateParseException extends Exception {
+    // The part of the candidate in which parsing failed (e.g. "port")
+    private final String field;
+    // The byte offset in the line at which the field starts, null if no line
+    // was parsed
+    private final Integer offset;
+    // The line number (starting at 1) when parsing a session description
+    private final Integer line;
+
+    public CandidateParseException(String message, String field) {
+        super(message);
+        this.field = field;
+        this.offset = null;
+        this.line = null;
+    }
+
+    public CandidateParseException(String message, String field, int offset) {
+        super(message);
+        this.field = field;
+        this.offset = offset;
+        this.line = null;
+    }
+
+    public CandidateParseException(String message, String field, int offset, int line) {
+        super(message);
+        this.field = field;
+        this.offset = offset;
+        this.line = line;
+    }
+
+    public String getField() {
+        return field;
+    }
+
+    /**
+     * Return the byte offset in the line at which the invalid field starts,
+     * or null if the field of an {@link IceCandidate} is invalid.
+     */
+    public Integer getOffset() {
+        return offset;
+    }
+
+    /**
+     * Return the line number (starting at 1) of the invalid candidate if a
+     * session description was parsed, or null.
+     */
+    public Integer getLine() {
+        return line;
+    }
+}
@@ -1,11 +1,35 @@
 package ch.dbrgn.candidateparser;
 
+import java.util.List;
+
 public class CandidateParser {
 
     static {
         System.loadLibrary("candidateparser_jni");
     }
 
-    public static native IceCandidate parseSdp(String sdp);
+    public static native IceCandidate parseSdp(String sdp) throws CandidateParseException;
+
+    /**
+     * Parse all candidates (a=candidate lines) in a session description.
+     *
+     * Invalid candidates are skipped. The sdpMid and sdpMLineIndex of the
+     * candidates are set if they are part of a media description.
+     */
+    public static List<IceCandidate> parseSessionDescription(String sdp) {
+        return parseSessionDescription(sdp, null);
+    }
+
+    /**
+     * Parse all candidates (a=candidate lines) in a session description.
+     *
+     * Invalid candidates do not prevent the other candidates from being
+     * returned: Their parse errors, containing the line number, are added to
+     * the errors list unless it is null. The sdpMid and sdpMLineIndex of the
+     * candidates are set if they are part of a media description.
+     */
+    public static native List<IceCandidate> parseSessionDescription(String sdp, List<CandidateParseException> errors);
+
+    public static native String toSdp(IceCandidate candidate) throws CandidateParseException;
 
 }
\ No newline at end of file
@@ -0,0 +1,40 @@
+package ch.dbrgn.candidateparser;
+
+/**
+ * The type of an ICE candidate.
+ */
+public enum CandidateType {
+    HOST("host"),
+    SRFLX("srflx"),
+    PRFLX("prflx"),
+    RELAY("relay"),
+    /**
+     * Any other type. The name can be found in the candidate type string.
+     */
+    OTHER(null);
+
+    private final String name;
+
+    CandidateType(String name) {
+        this.name = name;
+    }
+
+    /**
+     * Return the name used in the SDP (e.g. "srflx"), or null for OTHER.
+     */
+    public String getName() {
+        return name;
+    }
+
+    /**
+     * Return the candidate type for the specified name.
+     */
+    public static CandidateType fromString(String candidateType) {
+        for (CandidateType type : values()) {
+            if (type.name != null && type.name.equals(candidateType)) {
+                return type;
+            }
+        }
+        return OTHER;
+    }
+}
@@ -0,0 +1,57 @@
+package ch.dbrgn.candidateparser;
+
+import java.nio.charset.Charset;
+import java.util.Arrays;
+
+/**
+ * An extension attribute of an ICE candidate.
+ *
+ * The key and value are byte strings and may contain arbitrary bytes except
+ * for space, tab, NUL, CR and LF.
+ */
+public class Extension {
+    static final Charset UTF_8 = Charset.forName("UTF-8");
+
+    private final byte[] key;
+    private final byte[] value;
+
+    public Extension(byte[] key, byte[] value) {
+        this.key = key;
+        this.value = value;
+    }
+
+    public byte[] getKey() {
+        return key;
+    }
+
+    public byte[] getValue() {
+        return value;
+    }
+
+    /**
+     * Return the key decoded as UTF-8. Invalid bytes are replaced.
+     */
+    public String getKeyString() {
+        return new String(key, UTF_8);
+    }
+
+    /**
+     * Return the value decoded as UTF-8. Invalid bytes are replaced.
+     */
+    public String getValueString() {
+        return new String(value, UTF_8);
+    }
+
+    @Override
+    public boolean equals(Object o) {
+        if (this == o) return true;
+        if (!(o instanceof Extension)) return false;
+        final Extension other = (Extension) o;
+        return Arrays.equals(key, other.key) && Arrays.equals(value, other.value);
+    }
+
+    @Override
+    public int hashCode() {
+        return 31 * Arrays.hashCode(key) + Arrays.hashCode(value);
+    }
+}
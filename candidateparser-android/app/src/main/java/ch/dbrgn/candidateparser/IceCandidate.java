@@ -1,6 +1,12 @@
 package ch.dbrgn.candidateparser;
 
+import java.net.InetAddress;
+import java.net.UnknownHostException;
+import java.util.ArrayList;
+import java.util.Arrays;
+import java.util.Collections;
 import java.util.HashMap;
+import java.util.List;
 
 /**
  * A parsed ICE candidate POJO.
@@ -17,15 +23,19 @@ public class IceCandidate {
 
     // Extensions
     // Note: In theory the extension fields are byte arrays, not strings.
-    // But since Java cannot use byte[] as map key, and because the Java
-    // libwebrtc bindings return a String for the candidate SDP anyways,
-    // we'll use strings.
+    // Since Java cannot use byte[] as map key, the map contains the keys and
+    // values decoded as UTF-8. The raw bytes are kept in the extension list.
     private HashMap<String, String> extensions = new HashMap<>();
+    private ArrayList<Extension> extensionList = new ArrayList<>();
 
     // Nullable fields
     private String relAddr = null;
     private Integer relPort = null;
 
+    // Media description, only set by CandidateParser.parseSessionDescription
+    private String sdpMid = null;
+    private Integer sdpMLineIndex = null;
+
     public IceCandidate() {
     }
 
@@ -46,30 +56,249 @@ public class IceCandidate {
         return foundation;
     }
 
+    public void setFoundation(String foundation) {
+        this.foundation = foundation;
+    }
+
     public long getComponentId() {
         return componentId;
     }
 
+    public void setComponentId(long componentId) {
+        this.componentId = componentId;
+    }
+
     public String getTransport() {
         return transport;
     }
 
+    public void setTransport(String transport) {
+        this.transport = transport;
+    }
+
     public long getPriority() {
         return priority;
     }
 
+    public void setPriority(long priority) {
+        this.priority = priority;
+    }
+
     public String getConnectionAddress() {
         return connectionAddress;
     }
 
+    public void setConnectionAddress(String connectionAddress) {
+        this.connectionAddress = connectionAddress;
+    }
+
     public int getPort() {
         return port;
     }
 
+    public void setPort(int port) {
+        this.port = port;
+    }
+
     public String getCandidateType() {
         return candidateType;
     }
 
+    public void setCandidateType(String candidateType) {
+        this.candidateType = candidateType;
+    }
+
+    /**
+     * Return the transport as enum.
+     */
+    public Transport getTypedTransport() {
+        return Transport.fromString(transport);
+    }
+
+    /**
+     * Set the transport from an enum.
+     *
+     * @throws NullPointerException if the transport is null.
+     * @throws IllegalArgumentException if the transport is OTHER.
+     */
+    public void setTypedTransport(Transport transport) {
+        if (transport == null) {
+            throw new NullPointerException("Missing transport");
+        }
+        if (transport == Transport.OTHER) {
+            throw new IllegalArgumentException("Use the transport string to set another transport");
+        }
+        this.transport = transport.getName();
+    }
+
+    /**
+     * Return the candidate type as enum.
+     */
+    public CandidateType getTypedCandidateType() {
+        return CandidateType.fromString(candidateType);
+    }
+
+    /**
+     * Set the candidate type from an enum.
+     *
+     * @throws NullPointerException if the candidate type is null.
+     * @throws IllegalArgumentException if the candidate type is OTHER.
+     */
+    public void setTypedCandidateType(CandidateType candidateType) {
+        if (candidateType == null) {
+            throw new NullPointerException("Missing candidate type");
+        }
+        if (candidateType == CandidateType.OTHER) {
+            throw new IllegalArgumentException("Use the candidate type string to set another type");
+        }
+        this.candidateType = candidateType.getName();
+    }
+
+    /**
+     * Return the connection address as InetAddress, or null if it is not an
+     * IP address (e.g. an mDNS hostname). No name resolution is done.
+     */
+    public InetAddress getTypedConnectionAddress() {
+        return parseAddress(connectionAddress);
+    }
+
+    /**
+     * Set the connection address from an InetAddress.
+     *
+     * @throws IllegalArgumentException if the address is a scoped IPv6 address.
+     */
+    public void setTypedConnectionAddress(InetAddress connectionAddress) {
+        this.connectionAddress = hostAddress(connectionAddress);
+    }
+
+    /**
+     * Return the related address as InetAddress, or null if it is not
+     * defined or not an IP address. No name resolution is done.
+     */
+    public InetAddress getTypedRelAddr() {
+        return parseAddress(relAddr);
+    }
+
+    /**
+     * Set the related address from an InetAddress, or remove it if null.
+     *
+     * @throws IllegalArgumentException if the address is a scoped IPv6 address.
+     */
+    public void setTypedRelAddr(InetAddress relAddr) {
+        this.relAddr = relAddr == null ? null : hostAddress(relAddr);
+    }
+
+    /**
+     * Return the literal of an IP address. Scoped IPv6 addresses are rejected,
+     * since the zone index is not part of a candidate.
+     */
+    private static String hostAddress(InetAddress address) {
+        final String literal = address.getHostAddress();
+        if (literal.contains("%")) {
+            throw new IllegalArgumentException("Scoped IPv6 addresses are not supported");
+        }
+        return literal;
+    }
+
+    /**
+     * Parse an IP address literal. Return null for anything else. The
+     * literal is converted to bytes, so InetAddress never looks up a name.
+     */
+    private static InetAddress parseAddress(String address) {
+        if (address == null) {
+            return null;
+        }
+        final byte[] bytes = address.contains(":") ? parseIpv6(address) : parseIpv4(address);
+        if (bytes == null) {
+            return null;
+        }
+        try {
+            return InetAddress.getByAddress(bytes);
+        } catch (UnknownHostException e) {
+            return null;
+        }
+    }
+
+    /**
+     * Parse a dotted-quad IPv4 literal, or return null.
+     */
+    private static byte[] parseIpv4(String address) {
+        final String[] parts = address.split("\\.", -1);
+        if (parts.length != 4) {
+            return null;
+        }
+        final byte[] bytes = new byte[4];
+        for (int i = 0; i < 4; i++) {
+            if (!parts[i].matches("[0-9]{1,3}") || Integer.parseInt(parts[i]) > 255) {
+                return null;
+            }
+            bytes[i] = (byte) Integer.parseInt(parts[i]);
+        }
+        return bytes;
+    }
+
+    /**
+     * Parse an IPv6 literal (RFC 4291 section 2.2), or return null.
+     */
+    private static byte[] parseIpv6(String address) {
+        final int compressed = address.indexOf("::");
+        if (compressed != address.lastIndexOf("::")) {
+            return null;
+        }
+        final List<Integer> head = new ArrayList<>();
+        final List<Integer> tail = new ArrayList<>();
+        if (compressed < 0) {
+            if (!parseIpv6Groups(address, head, true)) {
+                return null;
+            }
+        } else if (!parseIpv6Groups(address.substring(0, compressed), head, false)
+                || !parseIpv6Groups(address.substring(compressed + 2), tail, true)) {
+            return null;
+        }
+        final int groups = head.size() + tail.size();
+        if (compressed < 0 ? groups != 8 : groups > 7) {
+            return null;
+        }
+        final byte[] bytes = new byte[16];
+        for (int i = 0; i < head.size(); i++) {
+            bytes[2 * i] = (byte) (head.get(i) >> 8);
+            bytes[2 * i + 1] = (byte) (int) head.get(i);
+        }
+        for (int i = 0; i < tail.size(); i++) {
+            final int offset = 2 * (8 - tail.size() + i);
+            bytes[offset] = (byte) (tail.get(i) >> 8);
+            bytes[offset + 1] = (byte) (int) tail.get(i);
+        }
+        return bytes;
+    }
+
+    /**
+     * Parse colon separated groups of up to four hex digits and add them to
+     * the list. If they are at the end of the address, the last group may be
+     * an IPv4 address. Return whether the groups are valid.
+     */
+    private static boolean parseIpv6Groups(String groups, List<Integer> result, boolean end) {
+        if (groups.isEmpty()) {
+            return true;
+        }
+        final String[] parts = groups.split(":", -1);
+        for (int i = 0; i < parts.length; i++) {
+            if (end && i == parts.length - 1 && parts[i].contains(".")) {
+                final byte[] ipv4 = parseIpv4(parts[i]);
+                if (ipv4 == null) {
+                    return false;
+                }
+                result.add((ipv4[0] & 0xff) << 8 | (ipv4[1] & 0xff));
+                result.add((ipv4[2] & 0xff) << 8 | (ipv4[3] & 0xff));
+            } else if (parts[i].matches("[0-9a-fA-F]{1,4}")) {
+                result.add(Integer.parseInt(parts[i], 16));
+            } else {
+                return false;
+            }
+        }
+        return true;
+    }
+
     public void setRelAddr(String relAddr) {
         this.relAddr = relAddr;
     }
@@ -86,8 +315,56 @@ public class IceCandidate {
         return relPort;
     }
 
+    public void setSdpMid(String sdpMid) {
+        this.sdpMid = sdpMid;
+    }
+
+    /**
+     * Return the identification tag (a=mid) of the media description
+     * containing the candidate, or null.
+     */
+    public String getSdpMid() {
+        return sdpMid;
+    }
+
+    public void setSdpMLineIndex(int sdpMLineIndex) {
+        this.sdpMLineIndex = sdpMLineIndex;
+    }
+
+    /**
+     * Return the index of the media description (m= line) containing the
+     * candidate, starting at 0, or null.
+     */
+    public Integer getSdpMLineIndex() {
+        return sdpMLineIndex;
+    }
+
     public void addExtension(String key, String val) {
-        this.extensions.put(key, val);
+        this.addExtensionBytes(key.getBytes(Extension.UTF_8), val.getBytes(Extension.UTF_8));
+    }
+
+    /**
+     * Add an extension with a raw byte string key and value. An existing
+     * extension with the same key is replaced.
+     */
+    public void addExtensionBytes(byte[] key, byte[] val) {
+        final Extension extension = new Extension(key, val);
+        final int index = this.indexOfExtension(key);
+        if (index < 0) {
+            this.extensionList.add(extension);
+        } else {
+            this.extensionList.set(index, extension);
+        }
+        this.extensions.put(extension.getKeyString(), extension.getValueString());
+    }
+
+    private int indexOfExtension(byte[] key) {
+        for (int i = 0; i < this.extensionList.size(); i++) {
+            if (Arrays.equals(this.extensionList.get(i).getKey(), key)) {
+                return i;
+            }
+        }
+        return -1;
     }
 
     public HashMap<String, String> getExtensions() {
@@ -97,4 +374,41 @@ public class IceCandidate {
     public String getExtension(String key) {
         return extensions.get(key);
     }
+
+    /**
+     * Return the raw value of the extension with the specified key, or null.
+     */
+    public byte[] getExtensionBytes(byte[] key) {
+        final int index = this.indexOfExtension(key);
+        return index < 0 ? null : this.extensionList.get(index).getValue();
+    }
+
+    /**
+     * Return the raw value of the extension with the specified (UTF-8
+     * encoded) key, or null.
+     */
+    public byte[] getExtensionBytes(String key) {
+        return this.getExtensionBytes(key.getBytes(Extension.UTF_8));
+    }
+
+    /**
+     * Return the extensions with their raw keys and values.
+     *
+     * Extensions parsed by CandidateParser are sorted by key, extensions
+     * added later are appended.
+     */
+    public List<Extension> getExtensionList() {
+        return Collections.unmodifiableList(extensionList);
+    }
+
+    /**
+     * Serialize the candidate to an SDP string.
+     *
+     * Extensions are written in lexicographical order of their keys.
+     *
+     * @throws CandidateParseException if the candidate is invalid.
+     */
+    public String toSdp() throws CandidateParseException {
+        return CandidateParser.toSdp(this);
+    }
 }
@@ -0,0 +1,32 @@
+package ch.dbrgn.candidateparser;
+
+/**
+ * The transport protocol of an ICE candidate.
+ */
+public enum Transport {
+    UDP("udp"),
+    /**
+     * Any other transport. The name can be found in the transport string.
+     */
+    OTHER(null);
+
+    private final String name;
+
+    Transport(String name) {
+        this.name = name;
+    }
+
+    /**
+     * Return the name used in the SDP (e.g. "udp"), or null for OTHER.
+     */
+    public String getName() {
+        return name;
+    }
+
+    /**
+     * Return the transport for the specified name (case insensitive).
+     */
+    public static Transport fromString(String transport) {
+        return "udp".equalsIgnoreCase(transport) ? UDP : OTHER;
+    }
+}
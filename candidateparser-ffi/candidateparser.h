@@ -6,13 +6,72 @@
 #ifndef candidateparser_bindings_h
 #define candidateparser_bindings_h
 
-/* Generated with cbindgen:0.1.23 */
-
 #include <stdint.h>
 #include <stdlib.h>
 #include <stdbool.h>
 
-// A key value pair.
+#define CANDIDATEPARSER_ABI_VERSION 1
+
+/*
+ * The family of an [`IpAddress`](struct.IpAddress.html).
+ */
+typedef enum {
+  /*
+   * No address is defined.
+   */
+  ADDRESS_FAMILY_NONE = 0,
+  ADDRESS_FAMILY_IPV4 = 4,
+  ADDRESS_FAMILY_IPV6 = 6,
+} AddressFamily;
+
+/*
+ * The type of a candidate.
+ */
+typedef enum {
+  ICE_CANDIDATE_TYPE_HOST = 0,
+  ICE_CANDIDATE_TYPE_SRFLX = 1,
+  ICE_CANDIDATE_TYPE_PRFLX = 2,
+  ICE_CANDIDATE_TYPE_RELAY = 3,
+  /*
+   * Any other type. The name can be found in the `candidate_type` string.
+   */
+  ICE_CANDIDATE_TYPE_OTHER = 4,
+} IceCandidateType;
+
+/*
+ * The transport protocol of a candidate.
+ */
+typedef enum {
+  ICE_TRANSPORT_UDP = 0,
+  /*
+   * Any other transport. The name can be found in the `transport` string.
+   */
+  ICE_TRANSPORT_OTHER = 1,
+} IceTransport;
+
+/*
+ * The result of a parse operation.
+ */
+typedef enum {
+  /*
+   * The candidate was parsed successfully.
+   */
+  PARSE_STATUS_OK = 0,
+  /*
+   * A null pointer was passed in.
+   */
+  PARSE_STATUS_NULL_POINTER = 1,
+  /*
+   * The candidate is invalid. Use
+   * [`candidateparser_last_error`](fn.candidateparser_last_error.html) to
+   * find out why.
+   */
+  PARSE_STATUS_PARSE_ERROR = 2,
+} ParseStatus;
+
+/*
+ * A key value pair.
+ */
 typedef struct {
   const uint8_t *key;
   size_t key_len;
@@ -20,16 +79,40 @@ typedef struct {
   size_t val_len;
 } KeyValuePair;
 
-// A key value map.
-// 
-// The `len` must be set to the length of the `values` array. Everything else
-// is undefined behavior!
+/*
+ * A key value map.
+ *
+ * The `len` must be set to the length of the `values` array. Everything else
+ * is undefined behavior!
+ */
 typedef struct {
   const KeyValuePair *values;
   size_t len;
 } KeyValueMap;
 
-// A wrapper around the `IceCandidate` data that is C compatible.
+/*
+ * An IP address in binary form.
+ *
+ * The `bytes` are in network byte order. IPv4 addresses only use the first 4
+ * bytes, the remaining bytes are set to `0`.
+ */
+typedef struct {
+  AddressFamily family;
+  uint8_t bytes[16];
+} IpAddress;
+
+/*
+ * A wrapper around the `IceCandidate` data that is C compatible.
+ *
+ * The `typed_*` fields contain the same data as the corresponding string
+ * fields in a typed form. They are derived from the strings and kept up to
+ * date by the `ice_candidate_set_*` functions.
+ *
+ * New fields are only ever appended to this struct, the fields of version
+ * 0.2.0 are still at the same offsets. Callers compiled against an older
+ * header can therefore safely access all fields they know about, as long as
+ * `struct_size` is at least `sizeof(IceCandidateFFI)` of their header.
+ */
 typedef struct {
   const char *foundation;
   uint32_t component_id;
@@ -38,31 +121,544 @@ typedef struct {
   const char *connection_address;
   uint16_t port;
   const char *candidate_type;
-  // The address is optional. If no value is defined, this will contain a
-  // null pointer.
+  /*
+   * The address is optional. If no value is defined, this will contain a
+   * null pointer and `has_rel_addr` is `false`.
+   */
   const char *rel_addr;
-  // This port is optional. If no address is defined, this will contain the
-  // value `0`.
+  /*
+   * This port is optional. If no port is defined, this will contain the
+   * value `0` and `has_rel_port` is `false`.
+   */
   uint16_t rel_port;
-  // The extensions map will always be defined but may be empty.
+  /*
+   * The extensions map will always be defined but may be empty. If it is
+   * empty, `values` is a null pointer and `has_extensions` is `false`.
+   */
   KeyValueMap extensions;
+  /*
+   * The transport as enum.
+   */
+  IceTransport typed_transport;
+  /*
+   * The candidate type as enum.
+   */
+  IceCandidateType typed_candidate_type;
+  /*
+   * The connection address in binary form. If the address is not defined
+   * or invalid, the family is `None`.
+   */
+  IpAddress typed_connection_address;
+  /*
+   * The related address in binary form. If the address is not defined
+   * or invalid, the family is `None`.
+   */
+  IpAddress typed_rel_addr;
+  /*
+   * Whether the related address is defined.
+   */
+  bool has_rel_addr;
+  /*
+   * Whether the related port is defined.
+   */
+  bool has_rel_port;
+  /*
+   * Whether the candidate has any extensions.
+   */
+  bool has_extensions;
+  /*
+   * Whether the `generation` extension is defined and a valid number.
+   */
+  bool has_generation;
+  /*
+   * The value of the `generation` extension, or `0` if `has_generation`
+   * is `false`.
+   */
+  uint32_t generation;
+  /*
+   * Whether the `network-id` extension is defined and a valid number.
+   */
+  bool has_network_id;
+  /*
+   * The value of the `network-id` extension, or `0` if `has_network_id`
+   * is `false`.
+   */
+  uint32_t network_id;
+  /*
+   * Whether the `network-cost` extension is defined and a valid number.
+   */
+  bool has_network_cost;
+  /*
+   * The value of the `network-cost` extension, or `0` if
+   * `has_network_cost` is `false`.
+   */
+  uint32_t network_cost;
+  /*
+   * The size of this struct in bytes, as known by the library that
+   * allocated it. Fields added later are appended after this field.
+   *
+   * Only read this field if
+   * [`candidateparser_abi_version`](fn.candidateparser_abi_version.html)
+   * returns at least `1`, older libraries don't provide it.
+   */
+  size_t struct_size;
 } IceCandidateFFI;
 
-// Free the memory associated with the [`IceCandidateFFI`](struct.IceCandidateFFI.html) struct.
-// 
-// Make sure to always call this function after you're done processing the
-// data, otherwise you'll end up with memory leaks!
-// 
-// This function is marked `unsafe` because it dereferences raw pointers.
+/*
+ * A candidate found in a session description.
+ */
+typedef struct {
+  /*
+   * Whether the candidate could be parsed.
+   */
+  ParseStatus status;
+  /*
+   * The parsed candidate. If the status is not `Ok`, this will contain a
+   * null pointer.
+   */
+  const IceCandidateFFI *candidate;
+  /*
+   * A message describing the error. If the status is `Ok`, this will
+   * contain a null pointer.
+   */
+  const char *error;
+  /*
+   * The line number, starting at 1.
+   */
+  size_t line;
+  /*
+   * Whether the candidate is part of a media description.
+   */
+  bool has_m_line_index;
+  /*
+   * The index of the media description (`m=` line) containing the
+   * candidate, starting at 0. If `has_m_line_index` is `false`, this will
+   * contain the value `0`.
+   */
+  size_t m_line_index;
+  /*
+   * The identification tag (`a=mid:`) of the media description. If no
+   * value is defined, this will contain a null pointer.
+   */
+  const char *mid;
+} SdpCandidateFFI;
+
+/*
+ * A list of candidates found in a session description.
+ *
+ * The `len` must be set to the length of the `entries` array. Everything else
+ * is undefined behavior! If the list is empty, `entries` is a null pointer.
+ */
+typedef struct {
+  const SdpCandidateFFI *entries;
+  size_t len;
+} SdpCandidateList;
+
+/*
+ * Return the ABI version of this library.
+ *
+ * If it differs from the
+ * [`CANDIDATEPARSER_ABI_VERSION`](constant.CANDIDATEPARSER_ABI_VERSION.html)
+ * constant of the header the caller was compiled against, the library is
+ * incompatible and must not be used.
+ */
+uint32_t candidateparser_abi_version();
+
+/*
+ * Return a message describing the error of the last failed call on the
+ * current thread (for example `Invalid port at offset 28`), or a null
+ * pointer if the last call succeeded.
+ *
+ * The functions reporting errors this way are
+ * [`try_parse_ice_candidate_sdp`](fn.try_parse_ice_candidate_sdp.html),
+ * [`try_parse_ice_candidate_sdp_bytes`](fn.try_parse_ice_candidate_sdp_bytes.html)
+ * and [`serialize_ice_candidate`](fn.serialize_ice_candidate.html).
+ *
+ * The returned string is owned by the library. It must not be freed and is
+ * only valid until the next call to one of these functions on the same
+ * thread.
+ */
+const char *candidateparser_last_error();
+
+/*
+ * Return the version of this library (e.g. `0.2.0`).
+ *
+ * The returned string is static and must not be freed.
+ */
+const char *candidateparser_version();
+
+/*
+ * Free the memory associated with the [`IceCandidateFFI`](struct.IceCandidateFFI.html) struct.
+ *
+ * Make sure to always call this function after you're done processing the
+ * data, otherwise you'll end up with memory leaks!
+ *
+ * ## Safety
+ *
+ * This function is marked `unsafe` because it dereferences raw pointers.
+ * `ptr` must be null or a candidate returned by this library that has not
+ * been freed yet.
+ */
 void free_ice_candidate(const IceCandidateFFI *ptr);
 
-// Parse an ICE candidate SDP string and return a pointer to an
-// [`IceCandidateFFI`](struct.IceCandidateFFI.html) struct.
-// 
-// Make sure to always call the [`free_ice_candidate`](fn.free_ice_candidate.html)
-// function after you're done processing the data, to prevent memory leaks!
-// 
-// This function is marked `unsafe` because it dereferences raw pointers.
+/*
+ * Free a string returned by [`serialize_ice_candidate`](fn.serialize_ice_candidate.html).
+ *
+ * ## Safety
+ *
+ * This function is marked `unsafe` because it dereferences raw pointers.
+ */
+void free_ice_candidate_sdp(char *sdp);
+
+/*
+ * Free the memory associated with the [`SdpCandidateList`](struct.SdpCandidateList.html)
+ * struct, including all candidates in it.
+ *
+ * ## Safety
+ *
+ * This function is marked `unsafe` because it dereferences raw pointers.
+ */
+void free_sdp_candidate_list(const SdpCandidateList *list);
+
+/*
+ * Remove the related port of the candidate.
+ *
+ * Return `false` if the candidate is a null pointer.
+ *
+ * ## Safety
+ *
+ * This function is marked `unsafe` because it dereferences raw pointers.
+ */
+bool ice_candidate_clear_rel_port(IceCandidateFFI *candidate);
+
+/*
+ * Set the type of the candidate (e.g. `host` or `srflx`).
+ *
+ * The string is copied. Return `false` if a null pointer or an invalid value
+ * was passed in, in which case the candidate is not modified.
+ *
+ * ## Safety
+ *
+ * This function is marked `unsafe` because it dereferences raw pointers.
+ * `value` must be null or point to a null terminated string.
+ */
+bool ice_candidate_set_candidate_type(IceCandidateFFI *candidate,
+                                      const char *value);
+
+/*
+ * Set the component ID of the candidate.
+ *
+ * Return `false` if the candidate is a null pointer.
+ *
+ * ## Safety
+ *
+ * This function is marked `unsafe` because it dereferences raw pointers.
+ */
+bool ice_candidate_set_component_id(IceCandidateFFI *candidate,
+                                    uint32_t value);
+
+/*
+ * Set the connection address of the candidate (e.g. `1.2.3.4` or `::1`).
+ *
+ * The string is copied. Return `false` if a null pointer or an invalid value
+ * was passed in, in which case the candidate is not modified.
+ *
+ * ## Safety
+ *
+ * This function is marked `unsafe` because it dereferences raw pointers.
+ * `value` must be null or point to a null terminated string.
+ */
+bool ice_candidate_set_connection_address(IceCandidateFFI *candidate,
+                                          const char *value);
+
+/*
+ * Add an extension to the candidate, or replace the value of an existing
+ * extension with the same key.
+ *
+ * The key and the value are copied. They must not be empty and must not
+ * contain spaces, tabs, CR, LF or null bytes. Return `false` if a null
+ * pointer or an invalid key or value was passed in.
+ *
+ * ## Safety
+ *
+ * This function is marked `unsafe` because it dereferences raw pointers.
+ * `key` and `val` must point to at least `key_len` and `val_len` bytes.
+ */
+bool ice_candidate_set_extension(IceCandidateFFI *candidate,
+                                 const uint8_t *key,
+                                 size_t key_len,
+                                 const uint8_t *val,
+                                 size_t val_len);
+
+/*
+ * Set the foundation of the candidate.
+ *
+ * The string is copied. Return `false` if a null pointer or an invalid value
+ * was passed in, in which case the candidate is not modified.
+ *
+ * ## Safety
+ *
+ * This function is marked `unsafe` because it dereferences raw pointers.
+ * `value` must be null or point to a null terminated string.
+ */
+bool ice_candidate_set_foundation(IceCandidateFFI *candidate,
+                                  const char *value);
+
+/*
+ * Set the port of the candidate.
+ *
+ * Return `false` if the candidate is a null pointer.
+ *
+ * ## Safety
+ *
+ * This function is marked `unsafe` because it dereferences raw pointers.
+ */
+bool ice_candidate_set_port(IceCandidateFFI *candidate,
+                            uint16_t value);
+
+/*
+ * Set the priority of the candidate.
+ *
+ * Return `false` if the candidate is a null pointer.
+ *
+ * ## Safety
+ *
+ * This function is marked `unsafe` because it dereferences raw pointers.
+ */
+bool ice_candidate_set_priority(IceCandidateFFI *candidate,
+                                uint64_t value);
+
+/*
+ * Set the related address of the candidate. Pass a null pointer to remove
+ * it.
+ *
+ * The string is copied. Return `false` if the candidate is a null pointer or
+ * if an invalid address was passed in, in which case the candidate is not
+ * modified.
+ *
+ * ## Safety
+ *
+ * This function is marked `unsafe` because it dereferences raw pointers.
+ * `value` must be null or point to a null terminated string.
+ */
+bool ice_candidate_set_rel_addr(IceCandidateFFI *candidate,
+                                const char *value);
+
+/*
+ * Set the related port of the candidate.
+ *
+ * Return `false` if the candidate is a null pointer.
+ *
+ * ## Safety
+ *
+ * This function is marked `unsafe` because it dereferences raw pointers.
+ */
+bool ice_candidate_set_rel_port(IceCandidateFFI *candidate,
+                                uint16_t value);
+
+/*
+ * Set the transport of the candidate (e.g. `udp`).
+ *
+ * The string is copied. Return `false` if a null pointer or an invalid value
+ * was passed in, in which case the candidate is not modified.
+ *
+ * ## Safety
+ *
+ * This function is marked `unsafe` because it dereferences raw pointers.
+ * `value` must be null or point to a null terminated string.
+ */
+bool ice_candidate_set_transport(IceCandidateFFI *candidate,
+                                 const char *value);
+
+/*
+ * Return the number of entries in the map. Return `0` if the map is a null
+ * pointer.
+ *
+ * ## Safety
+ *
+ * This function is marked `unsafe` because it dereferences raw pointers.
+ */
+size_t key_value_map_count(const KeyValueMap *map);
+
+/*
+ * Look up the value with the specified key in the map.
+ *
+ * If the key is found, a pointer to the value and its length are written to
+ * `val` and `val_len` and `true` is returned. Otherwise, `false` is
+ * returned. The value is owned by the map and is only valid as long as the
+ * candidate is not modified or freed.
+ *
+ * ## Safety
+ *
+ * This function is marked `unsafe` because it dereferences raw pointers.
+ * `key` must point to at least `key_len` bytes.
+ */
+bool key_value_map_get(const KeyValueMap *map,
+                       const uint8_t *key,
+                       size_t key_len,
+                       const uint8_t **val,
+                       size_t *val_len);
+
+/*
+ * Copy the entry at the specified index of the map to `pair`.
+ *
+ * Return `false` if the index is out of bounds or if a null pointer was
+ * passed in. The key and value are owned by the map and are only valid as
+ * long as the candidate is not modified or freed.
+ *
+ * ## Safety
+ *
+ * This function is marked `unsafe` because it dereferences raw pointers.
+ */
+bool key_value_map_get_index(const KeyValueMap *map,
+                             size_t index,
+                             KeyValuePair *pair);
+
+/*
+ * Allocate an empty [`IceCandidateFFI`](struct.IceCandidateFFI.html) struct.
+ *
+ * All numeric fields are set to `0`, all strings are null pointers and the
+ * extensions map is empty. The typed transport and candidate type are set to
+ * `Other`, the typed addresses have the family `None`. Use the `ice_candidate_set_*` functions to set
+ * the fields and [`serialize_ice_candidate`](fn.serialize_ice_candidate.html)
+ * to create the SDP string.
+ *
+ * The string fields and the extensions must only be modified through the
+ * setter functions, since they are owned by this library. The numeric
+ * fields may also be assigned directly, but the presence flags and the
+ * derived fields are only updated by the setter functions.
+ *
+ * Make sure to always call the [`free_ice_candidate`](fn.free_ice_candidate.html)
+ * function after you're done processing the data, to prevent memory leaks!
+ */
+IceCandidateFFI *new_ice_candidate();
+
+/*
+ * Parse an ICE candidate SDP string and return a pointer to an
+ * [`IceCandidateFFI`](struct.IceCandidateFFI.html) struct.
+ *
+ * Make sure to always call the [`free_ice_candidate`](fn.free_ice_candidate.html)
+ * function after you're done processing the data, to prevent memory leaks!
+ *
+ * If parsing fails, a null pointer is returned. Use
+ * [`try_parse_ice_candidate_sdp`](fn.try_parse_ice_candidate_sdp.html) to
+ * find out why parsing failed.
+ *
+ * ## Safety
+ *
+ * This function is marked `unsafe` because it dereferences raw pointers.
+ * `sdp` must be null or point to a null terminated string.
+ */
 const IceCandidateFFI *parse_ice_candidate_sdp(const char *sdp);
 
-#endif // candidateparser_bindings_h
+/*
+ * Parse an ICE candidate SDP from a buffer of `len` bytes and return a
+ * pointer to an [`IceCandidateFFI`](struct.IceCandidateFFI.html) struct.
+ *
+ * This works like [`parse_ice_candidate_sdp`](fn.parse_ice_candidate_sdp.html),
+ * but the buffer does not need to be null terminated. The buffer must
+ * contain exactly one candidate, without a line terminator.
+ *
+ * ## Safety
+ *
+ * This function is marked `unsafe` because it dereferences raw pointers.
+ * `data` must be null or point to at least `len` bytes.
+ */
+const IceCandidateFFI *parse_ice_candidate_sdp_bytes(const uint8_t *data,
+                                                     size_t len);
+
+/*
+ * Parse all candidates in a session description (or in a newline separated
+ * list of candidates) and return a pointer to an
+ * [`SdpCandidateList`](struct.SdpCandidateList.html) struct.
+ *
+ * Only `a=candidate:` and `candidate:` lines are parsed. Every entry of the
+ * list contains its own status, so invalid candidates do not prevent the
+ * other candidates from being returned. If a null pointer is passed in, a
+ * null pointer is returned.
+ *
+ * Make sure to always call the [`free_sdp_candidate_list`](fn.free_sdp_candidate_list.html)
+ * function after you're done processing the data, to prevent memory leaks!
+ *
+ * ## Safety
+ *
+ * This function is marked `unsafe` because it dereferences raw pointers.
+ * `sdp` must be null or point to a null terminated string.
+ */
+const SdpCandidateList *parse_sdp_candidates(const char *sdp);
+
+/*
+ * Parse all candidates in a session description from a buffer of `len`
+ * bytes and return a pointer to an
+ * [`SdpCandidateList`](struct.SdpCandidateList.html) struct.
+ *
+ * This works like [`parse_sdp_candidates`](fn.parse_sdp_candidates.html),
+ * but the buffer does not need to be null terminated.
+ *
+ * ## Safety
+ *
+ * This function is marked `unsafe` because it dereferences raw pointers.
+ * `data` must be null or point to at least `len` bytes.
+ */
+const SdpCandidateList *parse_sdp_candidates_bytes(const uint8_t *data,
+                                                   size_t len);
+
+/*
+ * Serialize the [`IceCandidateFFI`](struct.IceCandidateFFI.html) struct to
+ * an SDP string (e.g. `candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host`).
+ *
+ * The candidate may have been created using
+ * [`new_ice_candidate`](fn.new_ice_candidate.html) or
+ * [`parse_ice_candidate_sdp`](fn.parse_ice_candidate_sdp.html). Extensions
+ * are written in lexicographical order of their names.
+ *
+ * If the candidate is incomplete or invalid, a null pointer is returned and
+ * a message describing the error can be retrieved using
+ * [`candidateparser_last_error`](fn.candidateparser_last_error.html).
+ *
+ * Make sure to always call the [`free_ice_candidate_sdp`](fn.free_ice_candidate_sdp.html)
+ * function after you're done processing the string, to prevent memory leaks!
+ *
+ * ## Safety
+ *
+ * This function is marked `unsafe` because it dereferences raw pointers.
+ */
+char *serialize_ice_candidate(const IceCandidateFFI *candidate);
+
+/*
+ * Parse an ICE candidate SDP string and write a pointer to an
+ * [`IceCandidateFFI`](struct.IceCandidateFFI.html) struct to `candidate`.
+ *
+ * If the status is `Ok`, the candidate must be freed using the
+ * [`free_ice_candidate`](fn.free_ice_candidate.html) function. Otherwise,
+ * a null pointer is written to `candidate` (unless `candidate` itself is
+ * null) and a message describing the error can be retrieved using
+ * [`candidateparser_last_error`](fn.candidateparser_last_error.html).
+ *
+ * ## Safety
+ *
+ * This function is marked `unsafe` because it dereferences raw pointers.
+ * `sdp` must be null or point to a null terminated string.
+ */
+ParseStatus try_parse_ice_candidate_sdp(const char *sdp,
+                                        const IceCandidateFFI **candidate);
+
+/*
+ * Parse an ICE candidate SDP from a buffer of `len` bytes and write a
+ * pointer to an [`IceCandidateFFI`](struct.IceCandidateFFI.html) struct to
+ * `candidate`.
+ *
+ * This works like [`try_parse_ice_candidate_sdp`](fn.try_parse_ice_candidate_sdp.html),
+ * but the buffer does not need to be null terminated. The buffer must
+ * contain exactly one candidate, without a line terminator.
+ *
+ * ## Safety
+ *
+ * This function is marked `unsafe` because it dereferences raw pointers.
+ * `data` must be null or point to at least `len` bytes.
+ */
+ParseStatus try_parse_ice_candidate_sdp_bytes(const uint8_t *data,
+                                              size_t len,
+                                              const IceCandidateFFI **candidate);
+
+#endif /* candidateparser_bindings_h */
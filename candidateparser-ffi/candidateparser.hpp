@@ -157,9 +157,11 @@ namespace dbrgn
       , connection_address{m_rustData->connection_address}
       , port{m_rustData->port}
       , type{m_rustData->candidate_type}
-      , rel_address{m_rustData->rel_addr}
-      , rel_port{m_rustData->rel_port}
-      , extensions{internal::incarnate_map<decltype(extensions)::value_type>(m_rustData->extensions)}
+      , rel_address{m_rustData->has_rel_addr ? optional<string_view>{m_rustData->rel_addr} : optional<string_view>{}}
+      , rel_port{m_rustData->has_rel_port ? optional<std::uint16_t>{m_rustData->rel_port} : optional<std::uint16_t>{}}
+      , extensions{m_rustData->has_extensions
+                   ? decltype(extensions){internal::incarnate_map<decltype(extensions)::value_type>(m_rustData->extensions)}
+                   : decltype(extensions){}}
       { }
 
     std::unique_ptr<::IceCandidateFFI const, void (*)(::IceCandidateFFI const *)> m_rustData;
@@ -181,10 +183,28 @@ namespace dbrgn
             << "\tconnection_address : " << candidate.connection_address << '\n'
             << "\tport               : " << candidate.port << '\n'
             << "\ttype               : " << candidate.type << '\n'
-            << "\trel_address        : " << (candidate.rel_address ? candidate.rel_address.value() : "") << '\n'
-            << "\trel_port           : " << (candidate.rel_port ? candidate.rel_port.value() : '\0') << '\n'
             ;
 
+        out << "\trel_address        : ";
+        if(candidate.rel_address)
+          {
+          out << candidate.rel_address.value() << '\n';
+          }
+        else
+          {
+          out << "-\n";
+          }
+
+        out << "\trel_port           : ";
+        if(candidate.rel_port)
+          {
+          out << candidate.rel_port.value() << '\n';
+          }
+        else
+          {
+          out << "-\n";
+          }
+
         out << "\textensions         : ";
         if(candidate.extensions)
           {
@@ -40,6 +40,11 @@ static inline void print_bytes(uint8_t const *values, size_t len) {
 }
 
 int main() {
+    if (candidateparser_abi_version() != CANDIDATEPARSER_ABI_VERSION) {
+        fprintf(stderr, "Incompatible candidateparser library (version %s)\n", candidateparser_version());
+        return 1;
+    }
+
     const char *sdp = "candidate:842163049 1 udp 1686052607 1.2.3.4 46154 typ srflx raddr 10.0.0.17 rport 1337 generation 0 ufrag EEtu network-id 3 network-cost 10";
 
     printf("Parsing candidate:\n\n  %s\n", sdp);
@@ -55,23 +60,50 @@ int main() {
     printf("  Type:          %s\n", candidate->candidate_type);
     printf("  Rel Addr:      %s\n", candidate->rel_addr);
     printf("  Rel Port:      %hu\n", candidate->rel_port);
-    if (candidate->extensions.len <= 0) {
+    size_t extension_count = key_value_map_count(&candidate->extensions);
+    if (extension_count == 0) {
         printf("  Extensions:    -\n");
     } else {
         printf("  Extensions:\n");
-        for (size_t i = 0; i < candidate->extensions.len; i++) {
+        KeyValuePair pair;
+        for (size_t i = 0; i < extension_count; i++) {
+            key_value_map_get_index(&candidate->extensions, i, &pair);
             printf("    - ");
-            print_bytes(candidate->extensions.values[i].key,
-                        candidate->extensions.values[i].key_len);
+            print_bytes(pair.key, pair.key_len);
             printf(" => ");
-            print_bytes(candidate->extensions.values[i].val,
-                        candidate->extensions.values[i].val_len);
+            print_bytes(pair.val, pair.val_len);
             printf("\n");
         }
     }
+    const uint8_t *ufrag;
+    size_t ufrag_len;
+    if (key_value_map_get(&candidate->extensions, (const uint8_t *)"ufrag", 5, &ufrag, &ufrag_len)) {
+        printf("  Ufrag:         ");
+        print_bytes(ufrag, ufrag_len);
+        printf("\n");
+    }
 
     printf("\nCleaning up memory resources... ");
     free_ice_candidate(candidate);
+
+    printf("\n\nBuilding candidate:\n\n");
+    IceCandidateFFI *built = new_ice_candidate();
+    ice_candidate_set_foundation(built, "1");
+    ice_candidate_set_component_id(built, 1);
+    ice_candidate_set_transport(built, "udp");
+    ice_candidate_set_priority(built, 2130706431);
+    ice_candidate_set_connection_address(built, "10.0.0.17");
+    ice_candidate_set_port(built, 46154);
+    ice_candidate_set_candidate_type(built, "host");
+    ice_candidate_set_extension(built, (const uint8_t *)"generation", 10, (const uint8_t *)"0", 1);
+    char *built_sdp = serialize_ice_candidate(built);
+    if (built_sdp == NULL) {
+        printf("  Error: %s\n", candidateparser_last_error());
+    } else {
+        printf("  %s\n", built_sdp);
+    }
+    free_ice_candidate_sdp(built_sdp);
+    free_ice_candidate(built);
     printf("\n\nBrought to you by the powers of Rust!\n");
     return 0;
 }
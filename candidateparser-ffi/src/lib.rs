@@ -11,18 +11,36 @@
 extern crate candidateparser;
 extern crate libc;
 
-use libc::{c_char, size_t, uint8_t};
+use candidateparser::{IceCandidate, CandidateType, Transport, SdpCandidate, ExtensionMap, Field, ParseError};
+use libc::{c_char, size_t};
 use std::boxed::Box;
+use std::cell::RefCell;
 use std::ffi::{CStr, CString};
+use std::mem;
+use std::net::IpAddr;
 use std::ptr;
+use std::slice;
+
+/// The version of the ABI of this library.
+///
+/// It is incremented whenever a change breaks callers that were compiled
+/// against an older header, for example when a function signature changes or
+/// a field is removed. Appending fields to
+/// [`IceCandidateFFI`](struct.IceCandidateFFI.html) is not considered a
+/// breaking change, see its `struct_size` field.
+///
+/// Compare it with the value returned by
+/// [`candidateparser_abi_version`](fn.candidateparser_abi_version.html) to
+/// detect an incompatible library at runtime.
+pub const CANDIDATEPARSER_ABI_VERSION: u32 = 1;
 
 /// A key value pair.
 #[derive(Debug)]
 #[repr(C)]
 pub struct KeyValuePair {
-    pub key: *const uint8_t,
+    pub key: *const u8,
     pub key_len: size_t,
-    pub val: *const uint8_t,
+    pub val: *const u8,
     pub val_len: size_t,
 }
 
@@ -37,7 +55,58 @@ pub struct KeyValueMap {
     pub len: size_t,
 }
 
+/// The transport protocol of a candidate.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(C)]
+pub enum IceTransport {
+    Udp = 0,
+    /// Any other transport. The name can be found in the `transport` string.
+    Other = 1,
+}
+
+/// The type of a candidate.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(C)]
+pub enum IceCandidateType {
+    Host = 0,
+    Srflx = 1,
+    Prflx = 2,
+    Relay = 3,
+    /// Any other type. The name can be found in the `candidate_type` string.
+    Other = 4,
+}
+
+/// The family of an [`IpAddress`](struct.IpAddress.html).
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(C)]
+pub enum AddressFamily {
+    /// No address is defined.
+    None = 0,
+    Ipv4 = 4,
+    Ipv6 = 6,
+}
+
+/// An IP address in binary form.
+///
+/// The `bytes` are in network byte order. IPv4 addresses only use the first 4
+/// bytes, the remaining bytes are set to `0`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(C)]
+pub struct IpAddress {
+    pub family: AddressFamily,
+    pub bytes: [u8; 16],
+}
+
 /// A wrapper around the `IceCandidate` data that is C compatible.
+///
+/// The `typed_*` fields contain the same data as the corresponding string
+/// fields in a typed form. They are derived from the strings and kept up to
+/// date by the `ice_candidate_set_*` functions.
+///
+/// New fields are only ever appended to this struct, the fields of version
+/// 0.2.0 are still at the same offsets. Callers compiled against an older
+/// header can therefore safely access all fields they know about, as long as
+/// `struct_size` is at least `sizeof(IceCandidateFFI)` of their header.
 #[derive(Debug)]
 #[repr(C)]
 pub struct IceCandidateFFI {
@@ -49,37 +118,126 @@ pub struct IceCandidateFFI {
     pub port: u16,
     pub candidate_type: *const c_char,
     /// The address is optional. If no value is defined, this will contain a
-    /// null pointer.
+    /// null pointer and `has_rel_addr` is `false`.
     pub rel_addr: *const c_char,
-    /// This port is optional. If no address is defined, this will contain the
-    /// value `0`.
+    /// This port is optional. If no port is defined, this will contain the
+    /// value `0` and `has_rel_port` is `false`.
     pub rel_port: u16,
-    /// The extensions map will always be defined but may be empty.
+    /// The extensions map will always be defined but may be empty. If it is
+    /// empty, `values` is a null pointer and `has_extensions` is `false`.
     pub extensions: KeyValueMap,
+    /// The transport as enum.
+    pub typed_transport: IceTransport,
+    /// The candidate type as enum.
+    pub typed_candidate_type: IceCandidateType,
+    /// The connection address in binary form. If the address is not defined
+    /// or invalid, the family is `None`.
+    pub typed_connection_address: IpAddress,
+    /// The related address in binary form. If the address is not defined
+    /// or invalid, the family is `None`.
+    pub typed_rel_addr: IpAddress,
+    /// Whether the related address is defined.
+    pub has_rel_addr: bool,
+    /// Whether the related port is defined.
+    pub has_rel_port: bool,
+    /// Whether the candidate has any extensions.
+    pub has_extensions: bool,
+    /// Whether the `generation` extension is defined and a valid number.
+    pub has_generation: bool,
+    /// The value of the `generation` extension, or `0` if `has_generation`
+    /// is `false`.
+    pub generation: u32,
+    /// Whether the `network-id` extension is defined and a valid number.
+    pub has_network_id: bool,
+    /// The value of the `network-id` extension, or `0` if `has_network_id`
+    /// is `false`.
+    pub network_id: u32,
+    /// Whether the `network-cost` extension is defined and a valid number.
+    pub has_network_cost: bool,
+    /// The value of the `network-cost` extension, or `0` if
+    /// `has_network_cost` is `false`.
+    pub network_cost: u32,
+    /// The size of this struct in bytes, as known by the library that
+    /// allocated it. Fields added later are appended after this field.
+    ///
+    /// Only read this field if
+    /// [`candidateparser_abi_version`](fn.candidateparser_abi_version.html)
+    /// returns at least `1`, older libraries don't provide it.
+    pub struct_size: size_t,
 }
 
-/// Parse an ICE candidate SDP string and return a pointer to an
-/// [`IceCandidateFFI`](struct.IceCandidateFFI.html) struct.
-///
-/// Make sure to always call the [`free_ice_candidate`](fn.free_ice_candidate.html)
-/// function after you're done processing the data, to prevent memory leaks!
-///
-/// This function is marked `unsafe` because it dereferences raw pointers.
-#[no_mangle]
-pub unsafe extern "C" fn parse_ice_candidate_sdp(sdp: *const c_char) -> *const IceCandidateFFI {
-    // Convert C string to Rust byte slice
-    if sdp.is_null() {
-        return ptr::null();
+impl From<&Transport> for IceTransport {
+    fn from(transport: &Transport) -> Self {
+        match *transport {
+            Transport::Udp => IceTransport::Udp,
+            Transport::Extension(_) => IceTransport::Other,
+        }
     }
-    let cstr_sdp = CStr::from_ptr(sdp);
+}
 
-    // Parse
-    let parsed = match candidateparser::parse(cstr_sdp.to_bytes()) {
-        Some(candidate) => candidate,
-        None => return ptr::null(),
-    };
+impl From<&CandidateType> for IceCandidateType {
+    fn from(candidate_type: &CandidateType) -> Self {
+        match *candidate_type {
+            CandidateType::Host => IceCandidateType::Host,
+            CandidateType::Srflx => IceCandidateType::Srflx,
+            CandidateType::Prflx => IceCandidateType::Prflx,
+            CandidateType::Relay => IceCandidateType::Relay,
+            CandidateType::Token(_) => IceCandidateType::Other,
+        }
+    }
+}
 
-    // Convert to FFI representation
+impl From<Option<IpAddr>> for IpAddress {
+    fn from(addr: Option<IpAddr>) -> Self {
+        let mut bytes = [0; 16];
+        let family = match addr {
+            Some(IpAddr::V4(ip)) => {
+                bytes[..4].copy_from_slice(&ip.octets());
+                AddressFamily::Ipv4
+            },
+            Some(IpAddr::V6(ip)) => {
+                bytes.copy_from_slice(&ip.octets());
+                AddressFamily::Ipv6
+            },
+            None => AddressFamily::None,
+        };
+        IpAddress { family, bytes }
+    }
+}
+
+/// The result of a parse operation.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(C)]
+pub enum ParseStatus {
+    /// The candidate was parsed successfully.
+    Ok = 0,
+    /// A null pointer was passed in.
+    NullPointer = 1,
+    /// The candidate is invalid. Use
+    /// [`candidateparser_last_error`](fn.candidateparser_last_error.html) to
+    /// find out why.
+    ParseError = 2,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Store the error message, so that it can be returned by
+/// `candidateparser_last_error`.
+fn set_last_error(message: Option<String>) {
+    let message = message.map(|m| CString::new(m).expect("Error message contains a null byte"));
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = message);
+}
+
+/// Convert a parsed candidate into the FFI representation.
+fn candidate_to_ffi(parsed: IceCandidate) -> IceCandidateFFI {
+    let generation = parsed.generation();
+    let network_id = parsed.network_id();
+    let network_cost = parsed.network_cost();
+    let has_extensions = parsed.extensions.is_some();
+    let typed_transport = IceTransport::from(&parsed.transport);
+    let typed_candidate_type = IceCandidateType::from(&parsed.candidate_type);
     let transport_cstring: CString = parsed.transport.into();
     let candidate_type_cstring: CString = parsed.candidate_type.into();
     let extensions = match parsed.extensions {
@@ -90,30 +248,28 @@ pub unsafe extern "C" fn parse_ice_candidate_sdp(sdp: *const c_char) -> *const I
                 let k_len = k_vec.len();
                 let v_vec = v.clone();
                 let v_len = v_vec.len();
-                let pair = KeyValuePair {
+                KeyValuePair {
                     key: Box::into_raw(k_vec.into_boxed_slice()) as *const u8,
                     key_len: k_len,
                     val: Box::into_raw(v_vec.into_boxed_slice()) as *const u8,
                     val_len: v_len,
-                };
-                pair
+                }
             }).collect::<Vec<KeyValuePair>>();
             let extensions_len = extensions_vec.len();
 
             // Create KeyValueMap
-            let map = KeyValueMap {
+            KeyValueMap {
                 values: Box::into_raw(extensions_vec.into_boxed_slice()) as *const KeyValuePair,
                 len: extensions_len,
-            };
-
-            map
+            }
         },
         None => KeyValueMap {
             values: ptr::null(),
             len: 0,
         },
     };
-    let boxed = Box::new(IceCandidateFFI {
+    IceCandidateFFI {
+        struct_size: mem::size_of::<IceCandidateFFI>(),
         foundation: CString::new(parsed.foundation).unwrap().into_raw(),
         component_id: parsed.component_id,
         transport: transport_cstring.into_raw(),
@@ -126,10 +282,324 @@ pub unsafe extern "C" fn parse_ice_candidate_sdp(sdp: *const c_char) -> *const I
             None => ptr::null(),
         },
         rel_port: parsed.rel_port.unwrap_or(0),
-        extensions: extensions,
-    });
+        extensions,
+        typed_transport,
+        typed_candidate_type,
+        typed_connection_address: Some(parsed.connection_address).into(),
+        typed_rel_addr: parsed.rel_addr.into(),
+        has_rel_addr: parsed.rel_addr.is_some(),
+        has_rel_port: parsed.rel_port.is_some(),
+        has_extensions,
+        has_generation: generation.is_some(),
+        generation: generation.unwrap_or(0),
+        has_network_id: network_id.is_some(),
+        network_id: network_id.unwrap_or(0),
+        has_network_cost: network_cost.is_some(),
+        network_cost: network_cost.unwrap_or(0),
+    }
+}
+
+/// A candidate found in a session description.
+#[derive(Debug)]
+#[repr(C)]
+pub struct SdpCandidateFFI {
+    /// Whether the candidate could be parsed.
+    pub status: ParseStatus,
+    /// The parsed candidate. If the status is not `Ok`, this will contain a
+    /// null pointer.
+    pub candidate: *const IceCandidateFFI,
+    /// A message describing the error. If the status is `Ok`, this will
+    /// contain a null pointer.
+    pub error: *const c_char,
+    /// The line number, starting at 1.
+    pub line: size_t,
+    /// Whether the candidate is part of a media description.
+    pub has_m_line_index: bool,
+    /// The index of the media description (`m=` line) containing the
+    /// candidate, starting at 0. If `has_m_line_index` is `false`, this will
+    /// contain the value `0`.
+    pub m_line_index: size_t,
+    /// The identification tag (`a=mid:`) of the media description. If no
+    /// value is defined, this will contain a null pointer.
+    pub mid: *const c_char,
+}
+
+/// A list of candidates found in a session description.
+///
+/// The `len` must be set to the length of the `entries` array. Everything else
+/// is undefined behavior! If the list is empty, `entries` is a null pointer.
+#[derive(Debug)]
+#[repr(C)]
+pub struct SdpCandidateList {
+    pub entries: *const SdpCandidateFFI,
+    pub len: size_t,
+}
+
+/// Parse an ICE candidate SDP string and return a pointer to an
+/// [`IceCandidateFFI`](struct.IceCandidateFFI.html) struct.
+///
+/// Make sure to always call the [`free_ice_candidate`](fn.free_ice_candidate.html)
+/// function after you're done processing the data, to prevent memory leaks!
+///
+/// If parsing fails, a null pointer is returned. Use
+/// [`try_parse_ice_candidate_sdp`](fn.try_parse_ice_candidate_sdp.html) to
+/// find out why parsing failed.
+///
+/// ## Safety
+///
+/// This function is marked `unsafe` because it dereferences raw pointers.
+/// `sdp` must be null or point to a null terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn parse_ice_candidate_sdp(sdp: *const c_char) -> *const IceCandidateFFI {
+    // Convert C string to Rust byte slice
+    if sdp.is_null() {
+        return ptr::null();
+    }
+    let cstr_sdp = CStr::from_ptr(sdp);
+
+    // Parse
+    let parsed = match candidateparser::parse(cstr_sdp.to_bytes()) {
+        Some(candidate) => candidate,
+        None => return ptr::null(),
+    };
+
+    // Convert to FFI representation
+    Box::into_raw(Box::new(candidate_to_ffi(parsed)))
+}
 
-    Box::into_raw(boxed)
+/// Parse an ICE candidate SDP string and write a pointer to an
+/// [`IceCandidateFFI`](struct.IceCandidateFFI.html) struct to `candidate`.
+///
+/// If the status is `Ok`, the candidate must be freed using the
+/// [`free_ice_candidate`](fn.free_ice_candidate.html) function. Otherwise,
+/// a null pointer is written to `candidate` (unless `candidate` itself is
+/// null) and a message describing the error can be retrieved using
+/// [`candidateparser_last_error`](fn.candidateparser_last_error.html).
+///
+/// ## Safety
+///
+/// This function is marked `unsafe` because it dereferences raw pointers.
+/// `sdp` must be null or point to a null terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn try_parse_ice_candidate_sdp(
+    sdp: *const c_char,
+    candidate: *mut *const IceCandidateFFI,
+) -> ParseStatus {
+    if candidate.is_null() {
+        set_last_error(Some("The candidate out-parameter is a null pointer".into()));
+        return ParseStatus::NullPointer;
+    }
+    *candidate = ptr::null();
+    if sdp.is_null() {
+        set_last_error(Some("The SDP string is a null pointer".into()));
+        return ParseStatus::NullPointer;
+    }
+    let cstr_sdp = CStr::from_ptr(sdp);
+    try_parse_into(cstr_sdp.to_bytes(), candidate)
+}
+
+/// Parse the candidate and write the result to the `candidate` out-parameter.
+unsafe fn try_parse_into(sdp: &[u8], candidate: *mut *const IceCandidateFFI) -> ParseStatus {
+    match candidateparser::try_parse(sdp) {
+        Ok(parsed) => {
+            set_last_error(None);
+            *candidate = Box::into_raw(Box::new(candidate_to_ffi(parsed)));
+            ParseStatus::Ok
+        },
+        Err(e) => {
+            set_last_error(Some(e.to_string()));
+            ParseStatus::ParseError
+        },
+    }
+}
+
+/// Parse an ICE candidate SDP from a buffer of `len` bytes and return a
+/// pointer to an [`IceCandidateFFI`](struct.IceCandidateFFI.html) struct.
+///
+/// This works like [`parse_ice_candidate_sdp`](fn.parse_ice_candidate_sdp.html),
+/// but the buffer does not need to be null terminated. The buffer must
+/// contain exactly one candidate, without a line terminator.
+///
+/// ## Safety
+///
+/// This function is marked `unsafe` because it dereferences raw pointers.
+/// `data` must be null or point to at least `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn parse_ice_candidate_sdp_bytes(data: *const u8, len: size_t) -> *const IceCandidateFFI {
+    if data.is_null() {
+        return ptr::null();
+    }
+    match candidateparser::parse(slice::from_raw_parts(data, len)) {
+        Some(parsed) => Box::into_raw(Box::new(candidate_to_ffi(parsed))),
+        None => ptr::null(),
+    }
+}
+
+/// Parse an ICE candidate SDP from a buffer of `len` bytes and write a
+/// pointer to an [`IceCandidateFFI`](struct.IceCandidateFFI.html) struct to
+/// `candidate`.
+///
+/// This works like [`try_parse_ice_candidate_sdp`](fn.try_parse_ice_candidate_sdp.html),
+/// but the buffer does not need to be null terminated. The buffer must
+/// contain exactly one candidate, without a line terminator.
+///
+/// ## Safety
+///
+/// This function is marked `unsafe` because it dereferences raw pointers.
+/// `data` must be null or point to at least `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn try_parse_ice_candidate_sdp_bytes(
+    data: *const u8,
+    len: size_t,
+    candidate: *mut *const IceCandidateFFI,
+) -> ParseStatus {
+    if candidate.is_null() {
+        set_last_error(Some("The candidate out-parameter is a null pointer".into()));
+        return ParseStatus::NullPointer;
+    }
+    *candidate = ptr::null();
+    if data.is_null() {
+        set_last_error(Some("The SDP buffer is a null pointer".into()));
+        return ParseStatus::NullPointer;
+    }
+    try_parse_into(slice::from_raw_parts(data, len), candidate)
+}
+
+/// Convert the candidates found in a session description into the FFI
+/// representation.
+fn sdp_candidates_to_ffi(candidates: Vec<SdpCandidate>) -> *const SdpCandidateList {
+    let entries = candidates.into_iter().map(|c| {
+        let (status, candidate, error) = match c.result {
+            Ok(parsed) => (ParseStatus::Ok, Box::into_raw(Box::new(candidate_to_ffi(parsed))) as *const _, ptr::null()),
+            Err(e) => (ParseStatus::ParseError, ptr::null(), CString::new(e.to_string()).unwrap().into_raw() as *const _),
+        };
+        SdpCandidateFFI {
+            status,
+            candidate,
+            error,
+            line: c.line,
+            has_m_line_index: c.m_line_index.is_some(),
+            m_line_index: c.m_line_index.unwrap_or(0),
+            mid: match c.mid.and_then(|mid| CString::new(mid).ok()) {
+                Some(mid) => mid.into_raw(),
+                None => ptr::null(),
+            },
+        }
+    }).collect::<Vec<SdpCandidateFFI>>();
+    let len = entries.len();
+    let entries = if entries.is_empty() {
+        ptr::null()
+    } else {
+        Box::into_raw(entries.into_boxed_slice()) as *const SdpCandidateFFI
+    };
+    Box::into_raw(Box::new(SdpCandidateList { entries, len }))
+}
+
+/// Parse all candidates in a session description (or in a newline separated
+/// list of candidates) and return a pointer to an
+/// [`SdpCandidateList`](struct.SdpCandidateList.html) struct.
+///
+/// Only `a=candidate:` and `candidate:` lines are parsed. Every entry of the
+/// list contains its own status, so invalid candidates do not prevent the
+/// other candidates from being returned. If a null pointer is passed in, a
+/// null pointer is returned.
+///
+/// Make sure to always call the [`free_sdp_candidate_list`](fn.free_sdp_candidate_list.html)
+/// function after you're done processing the data, to prevent memory leaks!
+///
+/// ## Safety
+///
+/// This function is marked `unsafe` because it dereferences raw pointers.
+/// `sdp` must be null or point to a null terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn parse_sdp_candidates(sdp: *const c_char) -> *const SdpCandidateList {
+    if sdp.is_null() {
+        return ptr::null();
+    }
+    sdp_candidates_to_ffi(candidateparser::parse_sdp(CStr::from_ptr(sdp).to_bytes()))
+}
+
+/// Parse all candidates in a session description from a buffer of `len`
+/// bytes and return a pointer to an
+/// [`SdpCandidateList`](struct.SdpCandidateList.html) struct.
+///
+/// This works like [`parse_sdp_candidates`](fn.parse_sdp_candidates.html),
+/// but the buffer does not need to be null terminated.
+///
+/// ## Safety
+///
+/// This function is marked `unsafe` because it dereferences raw pointers.
+/// `data` must be null or point to at least `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn parse_sdp_candidates_bytes(data: *const u8, len: size_t) -> *const SdpCandidateList {
+    if data.is_null() {
+        return ptr::null();
+    }
+    sdp_candidates_to_ffi(candidateparser::parse_sdp(slice::from_raw_parts(data, len)))
+}
+
+/// Free the memory associated with the [`SdpCandidateList`](struct.SdpCandidateList.html)
+/// struct, including all candidates in it.
+///
+/// ## Safety
+///
+/// This function is marked `unsafe` because it dereferences raw pointers.
+#[no_mangle]
+pub unsafe extern "C" fn free_sdp_candidate_list(list: *const SdpCandidateList) {
+    if list.is_null() {
+        return;
+    }
+    let list = Box::from_raw(list as *mut SdpCandidateList);
+    if list.entries.is_null() {
+        return;
+    }
+    let entries = Vec::from_raw_parts(list.entries as *mut SdpCandidateFFI, list.len, list.len);
+    for entry in entries {
+        free_ice_candidate(entry.candidate);
+        free_cstring(entry.error);
+        free_cstring(entry.mid);
+    }
+}
+
+/// Return a message describing the error of the last failed call on the
+/// current thread (for example `Invalid port at offset 28`), or a null
+/// pointer if the last call succeeded.
+///
+/// The functions reporting errors this way are
+/// [`try_parse_ice_candidate_sdp`](fn.try_parse_ice_candidate_sdp.html),
+/// [`try_parse_ice_candidate_sdp_bytes`](fn.try_parse_ice_candidate_sdp_bytes.html)
+/// and [`serialize_ice_candidate`](fn.serialize_ice_candidate.html).
+///
+/// The returned string is owned by the library. It must not be freed and is
+/// only valid until the next call to one of these functions on the same
+/// thread.
+#[no_mangle]
+pub extern "C" fn candidateparser_last_error() -> *const c_char {
+    LAST_ERROR.with(|last_error| {
+        match *last_error.borrow() {
+            Some(ref message) => message.as_ptr(),
+            None => ptr::null(),
+        }
+    })
+}
+
+/// Return the version of this library (e.g. `0.2.0`).
+///
+/// The returned string is static and must not be freed.
+#[no_mangle]
+pub extern "C" fn candidateparser_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char
+}
+
+/// Return the ABI version of this library.
+///
+/// If it differs from the
+/// [`CANDIDATEPARSER_ABI_VERSION`](constant.CANDIDATEPARSER_ABI_VERSION.html)
+/// constant of the header the caller was compiled against, the library is
+/// incompatible and must not be used.
+#[no_mangle]
+pub extern "C" fn candidateparser_abi_version() -> u32 {
+    CANDIDATEPARSER_ABI_VERSION
 }
 
 /// Free the memory associated with the [`IceCandidateFFI`](struct.IceCandidateFFI.html) struct.
@@ -137,28 +607,592 @@ pub unsafe extern "C" fn parse_ice_candidate_sdp(sdp: *const c_char) -> *const I
 /// Make sure to always call this function after you're done processing the
 /// data, otherwise you'll end up with memory leaks!
 ///
+/// ## Safety
+///
 /// This function is marked `unsafe` because it dereferences raw pointers.
+/// `ptr` must be null or a candidate returned by this library that has not
+/// been freed yet.
 #[no_mangle]
 pub unsafe extern "C" fn free_ice_candidate(ptr: *const IceCandidateFFI) {
     if ptr.is_null() { return; }
     let ptr = ptr as *mut IceCandidateFFI;
-    let candidate: Box<IceCandidateFFI> = Box::from_raw(ptr);
-    CString::from_raw(candidate.foundation as *mut c_char);
-    CString::from_raw(candidate.transport as *mut c_char);
-    CString::from_raw(candidate.connection_address as *mut c_char);
-    CString::from_raw(candidate.candidate_type as *mut c_char);
-    if !candidate.rel_addr.is_null() {
-        CString::from_raw(candidate.rel_addr as *mut c_char);
-    }
-    let e = candidate.extensions;
-    let pairs = Vec::from_raw_parts(e.values as *mut KeyValuePair, e.len as usize, e.len as usize);
-    for p in pairs {
-        Vec::from_raw_parts(p.key as *mut uint8_t, p.key_len as usize, p.key_len as usize);
-        Vec::from_raw_parts(p.val as *mut uint8_t, p.val_len as usize, p.val_len as usize);
+    let mut candidate: Box<IceCandidateFFI> = Box::from_raw(ptr);
+    free_cstring(candidate.foundation);
+    free_cstring(candidate.transport);
+    free_cstring(candidate.connection_address);
+    free_cstring(candidate.candidate_type);
+    free_cstring(candidate.rel_addr);
+    for p in take_pairs(&mut candidate.extensions) {
+        Vec::from_raw_parts(p.key as *mut u8, p.key_len, p.key_len);
+        Vec::from_raw_parts(p.val as *mut u8, p.val_len, p.val_len);
     }
     // Resources will be freed here
 }
 
+/// Free a string allocated by this library. Null pointers are ignored.
+unsafe fn free_cstring(ptr: *const c_char) {
+    if !ptr.is_null() {
+        drop(CString::from_raw(ptr as *mut c_char));
+    }
+}
+
+/// Replace the string in `field` with a copy of `value`.
+unsafe fn replace_cstring(field: &mut *const c_char, value: *const c_char) {
+    free_cstring(*field);
+    *field = if value.is_null() {
+        ptr::null()
+    } else {
+        CStr::from_ptr(value).to_owned().into_raw()
+    };
+}
+
+/// Return the string as bytes, or `None` if it is a null pointer.
+unsafe fn cstring_bytes<'a>(ptr: *const c_char) -> Option<&'a [u8]> {
+    if ptr.is_null() {
+        None
+    } else {
+        Some(CStr::from_ptr(ptr).to_bytes())
+    }
+}
+
+/// Parse an IP address string.
+unsafe fn parse_address(ptr: *const c_char) -> Option<IpAddr> {
+    cstring_bytes(ptr)
+        .and_then(|bytes| std::str::from_utf8(bytes).ok())
+        .and_then(|string| string.parse().ok())
+}
+
+/// Derive the `typed_*` fields from the string fields.
+unsafe fn update_typed_fields(candidate: &mut IceCandidateFFI) {
+    candidate.typed_transport = match cstring_bytes(candidate.transport) {
+        Some(val) if val.eq_ignore_ascii_case(b"udp") => IceTransport::Udp,
+        _ => IceTransport::Other,
+    };
+    candidate.typed_candidate_type = match cstring_bytes(candidate.candidate_type) {
+        Some(b"host") => IceCandidateType::Host,
+        Some(b"srflx") => IceCandidateType::Srflx,
+        Some(b"prflx") => IceCandidateType::Prflx,
+        Some(b"relay") => IceCandidateType::Relay,
+        _ => IceCandidateType::Other,
+    };
+    candidate.typed_connection_address = parse_address(candidate.connection_address).into();
+    candidate.typed_rel_addr = parse_address(candidate.rel_addr).into();
+}
+
+/// Take ownership of the pairs in the map, leaving it empty.
+unsafe fn take_pairs(map: &mut KeyValueMap) -> Vec<KeyValuePair> {
+    let pairs = if map.values.is_null() {
+        vec![]
+    } else {
+        Vec::from_raw_parts(map.values as *mut KeyValuePair, map.len, map.len)
+    };
+    map.values = ptr::null();
+    map.len = 0;
+    pairs
+}
+
+/// Store the pairs in the map. The map must be empty.
+fn store_pairs(map: &mut KeyValueMap, pairs: Vec<KeyValuePair>) {
+    if pairs.is_empty() {
+        return;
+    }
+    map.len = pairs.len();
+    map.values = Box::into_raw(pairs.into_boxed_slice()) as *const KeyValuePair;
+}
+
+/// Return whether the byte can be part of an extension name or value.
+fn is_extension_byte(c: u8) -> bool {
+    !(c == 0x00 || c == b'\t' || c == b'\n' || c == b'\r' || c == b' ')
+}
+
+/// Allocate an empty [`IceCandidateFFI`](struct.IceCandidateFFI.html) struct.
+///
+/// All numeric fields are set to `0`, all strings are null pointers and the
+/// extensions map is empty. The typed transport and candidate type are set to
+/// `Other`, the typed addresses have the family `None`. Use the `ice_candidate_set_*` functions to set
+/// the fields and [`serialize_ice_candidate`](fn.serialize_ice_candidate.html)
+/// to create the SDP string.
+///
+/// The string fields and the extensions must only be modified through the
+/// setter functions, since they are owned by this library. The numeric
+/// fields may also be assigned directly, but the presence flags and the
+/// derived fields are only updated by the setter functions.
+///
+/// Make sure to always call the [`free_ice_candidate`](fn.free_ice_candidate.html)
+/// function after you're done processing the data, to prevent memory leaks!
+#[no_mangle]
+pub extern "C" fn new_ice_candidate() -> *mut IceCandidateFFI {
+    Box::into_raw(Box::new(IceCandidateFFI {
+        struct_size: mem::size_of::<IceCandidateFFI>(),
+        foundation: ptr::null(),
+        component_id: 0,
+        transport: ptr::null(),
+        priority: 0,
+        connection_address: ptr::null(),
+        port: 0,
+        candidate_type: ptr::null(),
+        rel_addr: ptr::null(),
+        rel_port: 0,
+        extensions: KeyValueMap {
+            values: ptr::null(),
+            len: 0,
+        },
+        typed_transport: IceTransport::Other,
+        typed_candidate_type: IceCandidateType::Other,
+        typed_connection_address: None.into(),
+        typed_rel_addr: None.into(),
+        has_rel_addr: false,
+        has_rel_port: false,
+        has_extensions: false,
+        has_generation: false,
+        generation: 0,
+        has_network_id: false,
+        network_id: 0,
+        has_network_cost: false,
+        network_cost: 0,
+    }))
+}
+
+/// Set the foundation of the candidate.
+///
+/// The string is copied. Return `false` if a null pointer or an invalid value
+/// was passed in, in which case the candidate is not modified.
+///
+/// ## Safety
+///
+/// This function is marked `unsafe` because it dereferences raw pointers.
+/// `value` must be null or point to a null terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn ice_candidate_set_foundation(candidate: *mut IceCandidateFFI, value: *const c_char) -> bool {
+    if candidate.is_null() || value.is_null() {
+        return false;
+    }
+    if candidateparser::parse_foundation(CStr::from_ptr(value).to_bytes()).is_err() {
+        return false;
+    }
+    replace_cstring(&mut (*candidate).foundation, value);
+    true
+}
+
+/// Set the component ID of the candidate.
+///
+/// Return `false` if the candidate is a null pointer.
+///
+/// ## Safety
+///
+/// This function is marked `unsafe` because it dereferences raw pointers.
+#[no_mangle]
+pub unsafe extern "C" fn ice_candidate_set_component_id(candidate: *mut IceCandidateFFI, value: u32) -> bool {
+    if candidate.is_null() {
+        return false;
+    }
+    (*candidate).component_id = value;
+    true
+}
+
+/// Set the transport of the candidate (e.g. `udp`).
+///
+/// The string is copied. Return `false` if a null pointer or an invalid value
+/// was passed in, in which case the candidate is not modified.
+///
+/// ## Safety
+///
+/// This function is marked `unsafe` because it dereferences raw pointers.
+/// `value` must be null or point to a null terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn ice_candidate_set_transport(candidate: *mut IceCandidateFFI, value: *const c_char) -> bool {
+    if candidate.is_null() || value.is_null() {
+        return false;
+    }
+    if candidateparser::parse_transport(CStr::from_ptr(value).to_bytes()).is_err() {
+        return false;
+    }
+    replace_cstring(&mut (*candidate).transport, value);
+    update_typed_fields(&mut *candidate);
+    true
+}
+
+/// Set the priority of the candidate.
+///
+/// Return `false` if the candidate is a null pointer.
+///
+/// ## Safety
+///
+/// This function is marked `unsafe` because it dereferences raw pointers.
+#[no_mangle]
+pub unsafe extern "C" fn ice_candidate_set_priority(candidate: *mut IceCandidateFFI, value: u64) -> bool {
+    if candidate.is_null() {
+        return false;
+    }
+    (*candidate).priority = value;
+    true
+}
+
+/// Set the connection address of the candidate (e.g. `1.2.3.4` or `::1`).
+///
+/// The string is copied. Return `false` if a null pointer or an invalid value
+/// was passed in, in which case the candidate is not modified.
+///
+/// ## Safety
+///
+/// This function is marked `unsafe` because it dereferences raw pointers.
+/// `value` must be null or point to a null terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn ice_candidate_set_connection_address(candidate: *mut IceCandidateFFI, value: *const c_char) -> bool {
+    if candidate.is_null() || value.is_null() {
+        return false;
+    }
+    if candidateparser::parse_address(Field::ConnectionAddress, CStr::from_ptr(value).to_bytes()).is_err() {
+        return false;
+    }
+    replace_cstring(&mut (*candidate).connection_address, value);
+    update_typed_fields(&mut *candidate);
+    true
+}
+
+/// Set the port of the candidate.
+///
+/// Return `false` if the candidate is a null pointer.
+///
+/// ## Safety
+///
+/// This function is marked `unsafe` because it dereferences raw pointers.
+#[no_mangle]
+pub unsafe extern "C" fn ice_candidate_set_port(candidate: *mut IceCandidateFFI, value: u16) -> bool {
+    if candidate.is_null() {
+        return false;
+    }
+    (*candidate).port = value;
+    true
+}
+
+/// Set the type of the candidate (e.g. `host` or `srflx`).
+///
+/// The string is copied. Return `false` if a null pointer or an invalid value
+/// was passed in, in which case the candidate is not modified.
+///
+/// ## Safety
+///
+/// This function is marked `unsafe` because it dereferences raw pointers.
+/// `value` must be null or point to a null terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn ice_candidate_set_candidate_type(candidate: *mut IceCandidateFFI, value: *const c_char) -> bool {
+    if candidate.is_null() || value.is_null() {
+        return false;
+    }
+    if candidateparser::parse_candidate_type(CStr::from_ptr(value).to_bytes()).is_err() {
+        return false;
+    }
+    replace_cstring(&mut (*candidate).candidate_type, value);
+    update_typed_fields(&mut *candidate);
+    true
+}
+
+/// Set the related port of the candidate.
+///
+/// Return `false` if the candidate is a null pointer.
+///
+/// ## Safety
+///
+/// This function is marked `unsafe` because it dereferences raw pointers.
+#[no_mangle]
+pub unsafe extern "C" fn ice_candidate_set_rel_port(candidate: *mut IceCandidateFFI, value: u16) -> bool {
+    if candidate.is_null() {
+        return false;
+    }
+    (*candidate).rel_port = value;
+    (*candidate).has_rel_port = true;
+    true
+}
+
+/// Remove the related port of the candidate.
+///
+/// Return `false` if the candidate is a null pointer.
+///
+/// ## Safety
+///
+/// This function is marked `unsafe` because it dereferences raw pointers.
+#[no_mangle]
+pub unsafe extern "C" fn ice_candidate_clear_rel_port(candidate: *mut IceCandidateFFI) -> bool {
+    if candidate.is_null() {
+        return false;
+    }
+    (*candidate).rel_port = 0;
+    (*candidate).has_rel_port = false;
+    true
+}
+
+/// Set the related address of the candidate. Pass a null pointer to remove
+/// it.
+///
+/// The string is copied. Return `false` if the candidate is a null pointer or
+/// if an invalid address was passed in, in which case the candidate is not
+/// modified.
+///
+/// ## Safety
+///
+/// This function is marked `unsafe` because it dereferences raw pointers.
+/// `value` must be null or point to a null terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn ice_candidate_set_rel_addr(candidate: *mut IceCandidateFFI, value: *const c_char) -> bool {
+    if candidate.is_null() {
+        return false;
+    }
+    if !value.is_null() && candidateparser::parse_address(Field::RelAddr, CStr::from_ptr(value).to_bytes()).is_err() {
+        return false;
+    }
+    replace_cstring(&mut (*candidate).rel_addr, value);
+    (*candidate).has_rel_addr = !value.is_null();
+    update_typed_fields(&mut *candidate);
+    true
+}
+
+/// Add an extension to the candidate, or replace the value of an existing
+/// extension with the same key.
+///
+/// The key and the value are copied. They must not be empty and must not
+/// contain spaces, tabs, CR, LF or null bytes. Return `false` if a null
+/// pointer or an invalid key or value was passed in.
+///
+/// ## Safety
+///
+/// This function is marked `unsafe` because it dereferences raw pointers.
+/// `key` and `val` must point to at least `key_len` and `val_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn ice_candidate_set_extension(
+    candidate: *mut IceCandidateFFI,
+    key: *const u8,
+    key_len: size_t,
+    val: *const u8,
+    val_len: size_t,
+) -> bool {
+    if candidate.is_null() || key.is_null() || val.is_null() || key_len == 0 || val_len == 0 {
+        return false;
+    }
+    let key = slice::from_raw_parts(key, key_len);
+    let val = slice::from_raw_parts(val, val_len);
+    if !key.iter().chain(val).all(|&c| is_extension_byte(c)) {
+        return false;
+    }
+
+    let extensions = &mut (*candidate).extensions;
+    let mut pairs = take_pairs(extensions);
+    let existing = pairs.iter_mut()
+        .find(|p| slice::from_raw_parts(p.key, p.key_len) == key);
+    let new_val = Box::into_raw(val.to_vec().into_boxed_slice()) as *const u8;
+    match existing {
+        Some(pair) => {
+            Vec::from_raw_parts(pair.val as *mut u8, pair.val_len, pair.val_len);
+            pair.val = new_val;
+            pair.val_len = val_len;
+        },
+        None => pairs.push(KeyValuePair {
+            key: Box::into_raw(key.to_vec().into_boxed_slice()) as *const u8,
+            key_len,
+            val: new_val,
+            val_len,
+        }),
+    }
+    store_pairs(extensions, pairs);
+    update_typed_extensions(&mut *candidate);
+    true
+}
+
+/// Return the value of the extension with the specified key.
+unsafe fn extension_value<'a>(map: &KeyValueMap, key: &[u8]) -> Option<&'a [u8]> {
+    if map.values.is_null() {
+        return None;
+    }
+    slice::from_raw_parts(map.values, map.len).iter()
+        .find(|p| slice::from_raw_parts(p.key, p.key_len) == key)
+        .map(|p| slice::from_raw_parts(p.val, p.val_len))
+}
+
+/// Derive the presence flags and values of the typed extensions.
+unsafe fn update_typed_extensions(candidate: &mut IceCandidateFFI) {
+    let number = |key: &[u8]| {
+        extension_value(&candidate.extensions, key)
+            .and_then(|v| std::str::from_utf8(v).ok())
+            .and_then(|v| v.parse::<u32>().ok())
+    };
+    let generation = number(b"generation");
+    let network_id = number(b"network-id");
+    let network_cost = number(b"network-cost");
+    candidate.has_extensions = candidate.extensions.len > 0;
+    candidate.has_generation = generation.is_some();
+    candidate.generation = generation.unwrap_or(0);
+    candidate.has_network_id = network_id.is_some();
+    candidate.network_id = network_id.unwrap_or(0);
+    candidate.has_network_cost = network_cost.is_some();
+    candidate.network_cost = network_cost.unwrap_or(0);
+}
+
+/// Return the number of entries in the map. Return `0` if the map is a null
+/// pointer.
+///
+/// ## Safety
+///
+/// This function is marked `unsafe` because it dereferences raw pointers.
+#[no_mangle]
+pub unsafe extern "C" fn key_value_map_count(map: *const KeyValueMap) -> size_t {
+    if map.is_null() || (*map).values.is_null() {
+        return 0;
+    }
+    (*map).len
+}
+
+/// Look up the value with the specified key in the map.
+///
+/// If the key is found, a pointer to the value and its length are written to
+/// `val` and `val_len` and `true` is returned. Otherwise, `false` is
+/// returned. The value is owned by the map and is only valid as long as the
+/// candidate is not modified or freed.
+///
+/// ## Safety
+///
+/// This function is marked `unsafe` because it dereferences raw pointers.
+/// `key` must point to at least `key_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn key_value_map_get(
+    map: *const KeyValueMap,
+    key: *const u8,
+    key_len: size_t,
+    val: *mut *const u8,
+    val_len: *mut size_t,
+) -> bool {
+    if map.is_null() || key.is_null() || val.is_null() || val_len.is_null() {
+        return false;
+    }
+    match extension_value(&*map, slice::from_raw_parts(key, key_len)) {
+        Some(value) => {
+            *val = value.as_ptr();
+            *val_len = value.len();
+            true
+        },
+        None => false,
+    }
+}
+
+/// Copy the entry at the specified index of the map to `pair`.
+///
+/// Return `false` if the index is out of bounds or if a null pointer was
+/// passed in. The key and value are owned by the map and are only valid as
+/// long as the candidate is not modified or freed.
+///
+/// ## Safety
+///
+/// This function is marked `unsafe` because it dereferences raw pointers.
+#[no_mangle]
+pub unsafe extern "C" fn key_value_map_get_index(
+    map: *const KeyValueMap,
+    index: size_t,
+    pair: *mut KeyValuePair,
+) -> bool {
+    if pair.is_null() || index >= key_value_map_count(map) {
+        return false;
+    }
+    let entry = &*(*map).values.add(index);
+    *pair = KeyValuePair {
+        key: entry.key,
+        key_len: entry.key_len,
+        val: entry.val,
+        val_len: entry.val_len,
+    };
+    true
+}
+
+/// Build an `IceCandidate` from the fields of the candidate and validate it.
+unsafe fn candidate_from_ffi(candidate: &IceCandidateFFI) -> Result<IceCandidate, String> {
+    let required = |field, ptr| cstring_bytes(ptr).ok_or_else(|| format!("Missing {}", field));
+    let invalid = |e: ParseError| format!("Invalid {}", e.field);
+
+    let foundation = candidateparser::parse_foundation(required(Field::Foundation, candidate.foundation)?)
+        .map_err(invalid)?;
+    let transport = candidateparser::parse_transport(required(Field::Transport, candidate.transport)?)
+        .map_err(invalid)?;
+    let connection_address = candidateparser::parse_address(
+        Field::ConnectionAddress, required(Field::ConnectionAddress, candidate.connection_address)?
+    ).map_err(invalid)?;
+    let candidate_type = candidateparser::parse_candidate_type(required(Field::CandidateType, candidate.candidate_type)?)
+        .map_err(invalid)?;
+    let rel_addr = match cstring_bytes(candidate.rel_addr) {
+        Some(addr) if candidate.has_rel_addr => {
+            Some(candidateparser::parse_address(Field::RelAddr, addr).map_err(invalid)?)
+        },
+        _ => None,
+    };
+    let e = &candidate.extensions;
+    let extensions = if e.values.is_null() {
+        None
+    } else {
+        Some(slice::from_raw_parts(e.values, e.len).iter()
+            .map(|p| (slice::from_raw_parts(p.key, p.key_len).to_vec(), slice::from_raw_parts(p.val, p.val_len).to_vec()))
+            .collect::<ExtensionMap>())
+    };
+
+    let parsed = IceCandidate {
+        foundation,
+        component_id: candidate.component_id,
+        transport,
+        priority: candidate.priority,
+        connection_address,
+        port: candidate.port,
+        candidate_type,
+        rel_addr,
+        rel_port: if candidate.has_rel_port { Some(candidate.rel_port) } else { None },
+        extensions,
+    };
+    parsed.validate().map_err(invalid)?;
+    Ok(parsed)
+}
+
+/// Serialize the [`IceCandidateFFI`](struct.IceCandidateFFI.html) struct to
+/// an SDP string (e.g. `candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host`).
+///
+/// The candidate may have been created using
+/// [`new_ice_candidate`](fn.new_ice_candidate.html) or
+/// [`parse_ice_candidate_sdp`](fn.parse_ice_candidate_sdp.html). Extensions
+/// are written in lexicographical order of their names.
+///
+/// If the candidate is incomplete or invalid, a null pointer is returned and
+/// a message describing the error can be retrieved using
+/// [`candidateparser_last_error`](fn.candidateparser_last_error.html).
+///
+/// Make sure to always call the [`free_ice_candidate_sdp`](fn.free_ice_candidate_sdp.html)
+/// function after you're done processing the string, to prevent memory leaks!
+///
+/// ## Safety
+///
+/// This function is marked `unsafe` because it dereferences raw pointers.
+#[no_mangle]
+pub unsafe extern "C" fn serialize_ice_candidate(candidate: *const IceCandidateFFI) -> *mut c_char {
+    if candidate.is_null() {
+        set_last_error(Some("The candidate is a null pointer".into()));
+        return ptr::null_mut();
+    }
+    match candidate_from_ffi(&*candidate) {
+        Ok(parsed) => {
+            let sdp = parsed.to_sdp();
+            set_last_error(None);
+            CString::new(sdp).expect("SDP contains a null byte").into_raw()
+        },
+        Err(e) => {
+            set_last_error(Some(e));
+            ptr::null_mut()
+        },
+    }
+}
+
+/// Free a string returned by [`serialize_ice_candidate`](fn.serialize_ice_candidate.html).
+///
+/// ## Safety
+///
+/// This function is marked `unsafe` because it dereferences raw pointers.
+#[no_mangle]
+pub unsafe extern "C" fn free_ice_candidate_sdp(sdp: *mut c_char) {
+    free_cstring(sdp);
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -194,4 +1228,310 @@ mod tests {
         assert_eq!(candidate.rel_port, 46154);
         assert_eq!(candidate.extensions.len, 4);
     }
+
+    #[test]
+    fn test_try_parse_ice_candidate_sdp() {
+        let sdp = CString::new("candidate:842163049 1 udp 1686052607 1.2.3.4 46154 typ host").unwrap();
+        let mut candidate: *const IceCandidateFFI = ptr::null();
+        let status = unsafe { try_parse_ice_candidate_sdp(sdp.as_ptr(), &mut candidate) };
+        assert_eq!(status, ParseStatus::Ok);
+        assert!(!candidate.is_null());
+        assert!(candidateparser_last_error().is_null());
+        assert_eq!(unsafe { (*candidate).port }, 46154);
+        unsafe { free_ice_candidate(candidate) };
+    }
+
+    #[test]
+    fn test_try_parse_ice_candidate_sdp_errors() {
+        let mut candidate: *const IceCandidateFFI = ptr::null();
+
+        // Null pointers
+        let status = unsafe { try_parse_ice_candidate_sdp(ptr::null(), &mut candidate) };
+        assert_eq!(status, ParseStatus::NullPointer);
+        assert!(candidate.is_null());
+        let sdp = CString::new("candidate:1 1 udp 1 1.2.3.4 65536 typ host").unwrap();
+        let status = unsafe { try_parse_ice_candidate_sdp(sdp.as_ptr(), ptr::null_mut()) };
+        assert_eq!(status, ParseStatus::NullPointer);
+
+        // Invalid candidate
+        let status = unsafe { try_parse_ice_candidate_sdp(sdp.as_ptr(), &mut candidate) };
+        assert_eq!(status, ParseStatus::ParseError);
+        assert!(candidate.is_null());
+        let message = unsafe { CStr::from_ptr(candidateparser_last_error()) };
+        assert_eq!(message.to_str().unwrap(), "Invalid port at offset 28");
+    }
+
+    #[test]
+    fn test_version() {
+        let version = unsafe { CStr::from_ptr(candidateparser_version()) };
+        assert_eq!(version.to_str().unwrap(), env!("CARGO_PKG_VERSION"));
+        assert_eq!(candidateparser_abi_version(), CANDIDATEPARSER_ABI_VERSION);
+
+        let sdp = CString::new("candidate:1 1 udp 1 1.2.3.4 5 typ host").unwrap();
+        let candidates = [unsafe { parse_ice_candidate_sdp(sdp.as_ptr()) }, new_ice_candidate()];
+        for &candidate in &candidates {
+            assert_eq!(unsafe { (*candidate).struct_size }, mem::size_of::<IceCandidateFFI>());
+            unsafe { free_ice_candidate(candidate) };
+        }
+    }
+
+    #[test]
+    fn test_layout_compatible_with_0_2_0() {
+        // The struct as defined in version 0.2.0
+        #[repr(C)]
+        #[allow(dead_code)]
+        struct IceCandidateFFI020 {
+            foundation: *const c_char,
+            component_id: u32,
+            transport: *const c_char,
+            priority: u64,
+            connection_address: *const c_char,
+            port: u16,
+            candidate_type: *const c_char,
+            rel_addr: *const c_char,
+            rel_port: u16,
+            extensions: KeyValueMap,
+        }
+        assert_eq!(mem::offset_of!(IceCandidateFFI, foundation), mem::offset_of!(IceCandidateFFI020, foundation));
+        assert_eq!(mem::offset_of!(IceCandidateFFI, component_id), mem::offset_of!(IceCandidateFFI020, component_id));
+        assert_eq!(mem::offset_of!(IceCandidateFFI, transport), mem::offset_of!(IceCandidateFFI020, transport));
+        assert_eq!(mem::offset_of!(IceCandidateFFI, priority), mem::offset_of!(IceCandidateFFI020, priority));
+        assert_eq!(mem::offset_of!(IceCandidateFFI, connection_address), mem::offset_of!(IceCandidateFFI020, connection_address));
+        assert_eq!(mem::offset_of!(IceCandidateFFI, port), mem::offset_of!(IceCandidateFFI020, port));
+        assert_eq!(mem::offset_of!(IceCandidateFFI, candidate_type), mem::offset_of!(IceCandidateFFI020, candidate_type));
+        assert_eq!(mem::offset_of!(IceCandidateFFI, rel_addr), mem::offset_of!(IceCandidateFFI020, rel_addr));
+        assert_eq!(mem::offset_of!(IceCandidateFFI, rel_port), mem::offset_of!(IceCandidateFFI020, rel_port));
+        assert_eq!(mem::offset_of!(IceCandidateFFI, extensions), mem::offset_of!(IceCandidateFFI020, extensions));
+        assert!(mem::offset_of!(IceCandidateFFI, typed_transport) >= mem::size_of::<IceCandidateFFI020>());
+    }
+
+    #[test]
+    fn test_serialize_ice_candidate() {
+        let candidate = new_ice_candidate();
+        let string = |s: &str| CString::new(s).unwrap();
+        unsafe {
+            // Incomplete candidate
+            assert!(serialize_ice_candidate(candidate).is_null());
+            let message = CStr::from_ptr(candidateparser_last_error());
+            assert_eq!(message.to_str().unwrap(), "Missing foundation");
+
+            assert!(ice_candidate_set_foundation(candidate, string("842163049").as_ptr()));
+            assert!(ice_candidate_set_component_id(candidate, 1));
+            assert!(ice_candidate_set_transport(candidate, string("udp").as_ptr()));
+            assert!(ice_candidate_set_priority(candidate, 1686052607));
+            assert!(ice_candidate_set_connection_address(candidate, string("1.2.3.4").as_ptr()));
+            assert!(ice_candidate_set_port(candidate, 46154));
+            assert!(ice_candidate_set_candidate_type(candidate, string("srflx").as_ptr()));
+            assert!(ice_candidate_set_rel_addr(candidate, string("10.0.0.17").as_ptr()));
+            assert!(ice_candidate_set_rel_port(candidate, 46154));
+            assert!(ice_candidate_set_extension(candidate, b"ufrag".as_ptr(), 5, b"EEtu".as_ptr(), 4));
+            assert!(ice_candidate_set_extension(candidate, b"generation".as_ptr(), 10, b"1".as_ptr(), 1));
+            assert!(ice_candidate_set_extension(candidate, b"generation".as_ptr(), 10, b"0".as_ptr(), 1));
+            assert!(!ice_candidate_set_extension(candidate, b"a b".as_ptr(), 3, b"0".as_ptr(), 1));
+            assert!(!ice_candidate_set_foundation(candidate, ptr::null()));
+            assert_eq!((*candidate).extensions.len, 2);
+
+            let sdp = serialize_ice_candidate(candidate);
+            assert_eq!(CStr::from_ptr(sdp).to_str().unwrap(),
+                       "candidate:842163049 1 udp 1686052607 1.2.3.4 46154 typ srflx \
+                        raddr 10.0.0.17 rport 46154 generation 0 ufrag EEtu");
+            assert!(candidateparser_last_error().is_null());
+            free_ice_candidate_sdp(sdp);
+
+            // Invalid values are rejected by the setters
+            assert!(!ice_candidate_set_connection_address(candidate, string("1.2.3").as_ptr()));
+            assert!(!ice_candidate_set_candidate_type(candidate, string("host generation 5").as_ptr()));
+            assert_eq!(CStr::from_ptr((*candidate).connection_address).to_str().unwrap(), "1.2.3.4");
+            assert_eq!(CStr::from_ptr((*candidate).candidate_type).to_str().unwrap(), "srflx");
+
+            // Numeric fields assigned directly are validated when serializing
+            (*candidate).component_id = 123456;
+            assert!(serialize_ice_candidate(candidate).is_null());
+            let message = CStr::from_ptr(candidateparser_last_error());
+            assert_eq!(message.to_str().unwrap(), "Invalid component-id");
+
+            free_ice_candidate(candidate);
+        }
+    }
+
+    #[test]
+    fn test_serialize_parsed_ice_candidate() {
+        let sdp = CString::new("candidate:1 1 UDP 2130706431 10.0.0.17 46154 typ host network-id 3").unwrap();
+        unsafe {
+            let candidate = parse_ice_candidate_sdp(sdp.as_ptr()) as *mut IceCandidateFFI;
+            assert!(ice_candidate_set_port(candidate, 1234));
+            let serialized = serialize_ice_candidate(candidate);
+            assert_eq!(CStr::from_ptr(serialized).to_str().unwrap(),
+                       "candidate:1 1 udp 2130706431 10.0.0.17 1234 typ host network-id 3");
+            free_ice_candidate_sdp(serialized);
+            free_ice_candidate(candidate);
+        }
+    }
+
+    #[test]
+    fn test_parse_ice_candidate_sdp_bytes() {
+        // The buffer is not null terminated
+        let data = b"candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host\r\ncandidate:2";
+        let len = data.len() - 13;
+        unsafe {
+            let candidate = parse_ice_candidate_sdp_bytes(data.as_ptr(), len);
+            assert!(!candidate.is_null());
+            assert_eq!((*candidate).port, 46154);
+            free_ice_candidate(candidate);
+            assert!(parse_ice_candidate_sdp_bytes(data.as_ptr(), len + 2).is_null());
+            assert!(parse_ice_candidate_sdp_bytes(ptr::null(), 0).is_null());
+
+            let mut candidate: *const IceCandidateFFI = ptr::null();
+            let status = try_parse_ice_candidate_sdp_bytes(data.as_ptr(), len, &mut candidate);
+            assert_eq!(status, ParseStatus::Ok);
+            assert_eq!((*candidate).port, 46154);
+            free_ice_candidate(candidate);
+            let status = try_parse_ice_candidate_sdp_bytes(data.as_ptr(), len - 5, &mut candidate);
+            assert_eq!(status, ParseStatus::ParseError);
+            assert!(candidate.is_null());
+            let message = CStr::from_ptr(candidateparser_last_error());
+            assert_eq!(message.to_str().unwrap(), "Invalid cand-type at offset 45");
+            let status = try_parse_ice_candidate_sdp_bytes(ptr::null(), 0, &mut candidate);
+            assert_eq!(status, ParseStatus::NullPointer);
+        }
+    }
+
+    #[test]
+    fn test_typed_fields() {
+        let sdp = CString::new("candidate:1 1 udp 1686052607 2001:db8::1 46154 typ srflx raddr 10.0.0.17 rport 46154").unwrap();
+        unsafe {
+            let candidate = parse_ice_candidate_sdp(sdp.as_ptr()) as *mut IceCandidateFFI;
+            assert_eq!((*candidate).typed_transport, IceTransport::Udp);
+            assert_eq!((*candidate).typed_candidate_type, IceCandidateType::Srflx);
+            assert_eq!((*candidate).typed_connection_address.family, AddressFamily::Ipv6);
+            assert_eq!((*candidate).typed_connection_address.bytes,
+                       [0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+            assert_eq!((*candidate).typed_rel_addr.family, AddressFamily::Ipv4);
+            assert_eq!((*candidate).typed_rel_addr.bytes, [10, 0, 0, 17, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+            // The setters update the typed fields
+            assert!(ice_candidate_set_transport(candidate, CString::new("tcp").unwrap().as_ptr()));
+            assert!(ice_candidate_set_candidate_type(candidate, CString::new("foo").unwrap().as_ptr()));
+            assert!(ice_candidate_set_connection_address(candidate, CString::new("1.2.3.4").unwrap().as_ptr()));
+            assert!(ice_candidate_set_rel_addr(candidate, ptr::null()));
+            assert_eq!((*candidate).typed_transport, IceTransport::Other);
+            assert_eq!((*candidate).typed_candidate_type, IceCandidateType::Other);
+            assert_eq!((*candidate).typed_connection_address.family, AddressFamily::Ipv4);
+            assert_eq!((*candidate).typed_connection_address.bytes, [1, 2, 3, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            assert_eq!((*candidate).typed_rel_addr.family, AddressFamily::None);
+
+            free_ice_candidate(candidate);
+        }
+    }
+
+    #[test]
+    fn test_presence_flags() {
+        let sdp = CString::new("candidate:1 1 udp 1686052607 1.2.3.4 46154 typ srflx rport 0 generation 1 network-cost x").unwrap();
+        unsafe {
+            let candidate = parse_ice_candidate_sdp(sdp.as_ptr()) as *mut IceCandidateFFI;
+            assert!(!(*candidate).has_rel_addr);
+            assert!((*candidate).has_rel_port);
+            assert_eq!((*candidate).rel_port, 0);
+            assert!((*candidate).has_extensions);
+            assert!((*candidate).has_generation);
+            assert_eq!((*candidate).generation, 1);
+            assert!(!(*candidate).has_network_id);
+            assert!(!(*candidate).has_network_cost);
+
+            // The setters update the flags
+            assert!(ice_candidate_clear_rel_port(candidate));
+            assert!(ice_candidate_set_rel_addr(candidate, CString::new("10.0.0.17").unwrap().as_ptr()));
+            assert!(ice_candidate_set_extension(candidate, b"network-cost".as_ptr(), 12, b"10".as_ptr(), 2));
+            assert!(!(*candidate).has_rel_port);
+            assert!((*candidate).has_rel_addr);
+            assert!((*candidate).has_network_cost);
+            assert_eq!((*candidate).network_cost, 10);
+            free_ice_candidate(candidate);
+
+            let candidate = new_ice_candidate();
+            assert!(!(*candidate).has_extensions);
+            assert!(ice_candidate_set_extension(candidate, b"network-id".as_ptr(), 10, b"3".as_ptr(), 1));
+            assert!((*candidate).has_extensions);
+            assert!((*candidate).has_network_id);
+            assert_eq!((*candidate).network_id, 3);
+            free_ice_candidate(candidate);
+        }
+    }
+
+    #[test]
+    fn test_key_value_map() {
+        let sdp = CString::new("candidate:1 1 udp 1 1.2.3.4 5 typ host generation 0 ufrag EEtu").unwrap();
+        unsafe {
+            let candidate = parse_ice_candidate_sdp(sdp.as_ptr());
+            let map = &(*candidate).extensions as *const KeyValueMap;
+            assert_eq!(key_value_map_count(map), 2);
+            assert_eq!(key_value_map_count(ptr::null()), 0);
+
+            let mut val: *const u8 = ptr::null();
+            let mut val_len: size_t = 0;
+            assert!(key_value_map_get(map, b"ufrag".as_ptr(), 5, &mut val, &mut val_len));
+            assert_eq!(slice::from_raw_parts(val, val_len), b"EEtu");
+            assert!(!key_value_map_get(map, b"ufra".as_ptr(), 4, &mut val, &mut val_len));
+
+            let mut pair = KeyValuePair { key: ptr::null(), key_len: 0, val: ptr::null(), val_len: 0 };
+            let mut keys = vec![];
+            for i in 0..2 {
+                assert!(key_value_map_get_index(map, i, &mut pair));
+                keys.push(slice::from_raw_parts(pair.key, pair.key_len).to_vec());
+            }
+            keys.sort();
+            assert_eq!(keys, vec![b"generation".to_vec(), b"ufrag".to_vec()]);
+            assert!(!key_value_map_get_index(map, 2, &mut pair));
+            free_ice_candidate(candidate);
+
+            // Empty map
+            let candidate = new_ice_candidate();
+            let map = &(*candidate).extensions as *const KeyValueMap;
+            assert_eq!(key_value_map_count(map), 0);
+            assert!(!key_value_map_get(map, b"ufrag".as_ptr(), 5, &mut val, &mut val_len));
+            assert!(!key_value_map_get_index(map, 0, &mut pair));
+            free_ice_candidate(candidate);
+        }
+    }
+
+    #[test]
+    fn test_parse_sdp_candidates() {
+        let sdp = CString::new("v=0\r\n\
+                                m=audio 9 UDP/TLS/RTP/SAVPF 111\r\n\
+                                a=mid:audio\r\n\
+                                a=candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host\r\n\
+                                a=candidate:2 1 udp 1686052607 1.2.3.4 x typ srflx\r\n").unwrap();
+        unsafe {
+            let list = parse_sdp_candidates(sdp.as_ptr());
+            assert_eq!((*list).len, 2);
+            let entries = slice::from_raw_parts((*list).entries, (*list).len);
+            assert_eq!(entries[0].status, ParseStatus::Ok);
+            assert_eq!((*entries[0].candidate).port, 46154);
+            assert!(entries[0].error.is_null());
+            assert_eq!(entries[0].line, 4);
+            assert!(entries[0].has_m_line_index);
+            assert_eq!(entries[0].m_line_index, 0);
+            assert_eq!(CStr::from_ptr(entries[0].mid).to_str().unwrap(), "audio");
+            assert_eq!(entries[1].status, ParseStatus::ParseError);
+            assert!(entries[1].candidate.is_null());
+            assert_eq!(CStr::from_ptr(entries[1].error).to_str().unwrap(), "Invalid port at offset 39");
+            assert_eq!(entries[1].line, 5);
+            free_sdp_candidate_list(list);
+
+            // Candidate list without media descriptions
+            let data = b"candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host";
+            let list = parse_sdp_candidates_bytes(data.as_ptr(), data.len());
+            assert_eq!((*list).len, 1);
+            assert!(!(*(*list).entries).has_m_line_index);
+            assert!((*(*list).entries).mid.is_null());
+            free_sdp_candidate_list(list);
+
+            // Empty list
+            let list = parse_sdp_candidates_bytes(data.as_ptr(), 0);
+            assert_eq!((*list).len, 0);
+            assert!((*list).entries.is_null());
+            free_sdp_candidate_list(list);
+            assert!(parse_sdp_candidates(ptr::null()).is_null());
+        }
+    }
 }
@@ -0,0 +1,250 @@
+/**
+ * Test harness for the C bindings.
+ *
+ * This program is compiled against the generated header and the static
+ * library by the `c_harness` integration test and is run with
+ * AddressSanitizer (including LeakSanitizer), so that any memory leak,
+ * double free or invalid access caused by the ownership rules of the
+ * bindings makes it fail.
+ *
+ * If the first argument is `leak`, a candidate is deliberately not freed to
+ * verify that leaks are detected.
+ */
+#include <stdio.h>
+#include <stdint.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "candidateparser.h"
+
+#define CHECK(cond) do { \
+    if (!(cond)) { \
+        fprintf(stderr, "%s:%d: Check failed: %s\n", __FILE__, __LINE__, #cond); \
+        exit(1); \
+    } \
+} while (0)
+
+static const char *FULL = "candidate:842163049 1 udp 1686052607 1.2.3.4 46154 typ srflx "
+                          "raddr 10.0.0.17 rport 46154 generation 0 ufrag EEtu network-id 3 network-cost 10";
+
+static void test_version(void) {
+    CHECK(candidateparser_abi_version() == CANDIDATEPARSER_ABI_VERSION);
+    CHECK(strlen(candidateparser_version()) > 0);
+
+    IceCandidateFFI *candidate = new_ice_candidate();
+    CHECK(candidate->struct_size == sizeof(IceCandidateFFI));
+    free_ice_candidate(candidate);
+}
+
+static void test_parse(void) {
+    const IceCandidateFFI *candidate = parse_ice_candidate_sdp(FULL);
+    CHECK(candidate != NULL);
+    CHECK(strcmp(candidate->foundation, "842163049") == 0);
+    CHECK(candidate->component_id == 1);
+    CHECK(strcmp(candidate->transport, "udp") == 0);
+    CHECK(candidate->typed_transport == ICE_TRANSPORT_UDP);
+    CHECK(candidate->priority == 1686052607);
+    CHECK(strcmp(candidate->connection_address, "1.2.3.4") == 0);
+    CHECK(candidate->typed_connection_address.family == ADDRESS_FAMILY_IPV4);
+    CHECK(memcmp(candidate->typed_connection_address.bytes, "\x01\x02\x03\x04", 4) == 0);
+    CHECK(candidate->port == 46154);
+    CHECK(strcmp(candidate->candidate_type, "srflx") == 0);
+    CHECK(candidate->typed_candidate_type == ICE_CANDIDATE_TYPE_SRFLX);
+    CHECK(candidate->has_rel_addr);
+    CHECK(strcmp(candidate->rel_addr, "10.0.0.17") == 0);
+    CHECK(candidate->has_rel_port);
+    CHECK(candidate->rel_port == 46154);
+    CHECK(candidate->has_extensions);
+    CHECK(candidate->has_generation && candidate->generation == 0);
+    CHECK(candidate->has_network_id && candidate->network_id == 3);
+    CHECK(candidate->has_network_cost && candidate->network_cost == 10);
+    free_ice_candidate(candidate);
+
+    // Without optional fields
+    candidate = parse_ice_candidate_sdp("candidate:1 1 udp 1 ::1 5 typ host");
+    CHECK(candidate != NULL);
+    CHECK(candidate->rel_addr == NULL && !candidate->has_rel_addr);
+    CHECK(!candidate->has_rel_port);
+    CHECK(candidate->extensions.values == NULL && !candidate->has_extensions);
+    CHECK(candidate->typed_connection_address.family == ADDRESS_FAMILY_IPV6);
+    free_ice_candidate(candidate);
+
+    CHECK(parse_ice_candidate_sdp("candidate:1 1 udp 1 1.2.3.4 x typ host") == NULL);
+    CHECK(parse_ice_candidate_sdp(NULL) == NULL);
+    free_ice_candidate(NULL);
+}
+
+static void test_try_parse(void) {
+    const IceCandidateFFI *candidate = NULL;
+    CHECK(try_parse_ice_candidate_sdp(FULL, &candidate) == PARSE_STATUS_OK);
+    CHECK(candidate != NULL);
+    CHECK(candidateparser_last_error() == NULL);
+    free_ice_candidate(candidate);
+
+    CHECK(try_parse_ice_candidate_sdp("candidate:1 1 udp 1 1.2.3.4 65536 typ host", &candidate) == PARSE_STATUS_PARSE_ERROR);
+    CHECK(candidate == NULL);
+    CHECK(strcmp(candidateparser_last_error(), "Invalid port at offset 28") == 0);
+
+    CHECK(try_parse_ice_candidate_sdp(NULL, &candidate) == PARSE_STATUS_NULL_POINTER);
+    CHECK(candidateparser_last_error() != NULL);
+    CHECK(try_parse_ice_candidate_sdp(FULL, NULL) == PARSE_STATUS_NULL_POINTER);
+}
+
+static void test_parse_bytes(void) {
+    // Not null terminated
+    const char buf[] = "candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host\r\nrest";
+    size_t len = strlen("candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host");
+    uint8_t *data = malloc(len);
+    memcpy(data, buf, len);
+
+    const IceCandidateFFI *candidate = parse_ice_candidate_sdp_bytes(data, len);
+    CHECK(candidate != NULL);
+    CHECK(candidate->port == 46154);
+    free_ice_candidate(candidate);
+
+    CHECK(try_parse_ice_candidate_sdp_bytes(data, len, &candidate) == PARSE_STATUS_OK);
+    free_ice_candidate(candidate);
+    CHECK(try_parse_ice_candidate_sdp_bytes(data, 5, &candidate) == PARSE_STATUS_PARSE_ERROR);
+    CHECK(candidate == NULL);
+
+    free(data);
+}
+
+static void test_build_and_serialize(void) {
+    IceCandidateFFI *candidate = new_ice_candidate();
+    CHECK(serialize_ice_candidate(candidate) == NULL);
+    CHECK(candidateparser_last_error() != NULL);
+
+    CHECK(ice_candidate_set_foundation(candidate, "1"));
+    CHECK(ice_candidate_set_component_id(candidate, 1));
+    CHECK(ice_candidate_set_transport(candidate, "udp"));
+    CHECK(ice_candidate_set_priority(candidate, 2130706431));
+    CHECK(ice_candidate_set_connection_address(candidate, "10.0.0.17"));
+    CHECK(ice_candidate_set_port(candidate, 46154));
+    CHECK(ice_candidate_set_candidate_type(candidate, "srflx"));
+    CHECK(ice_candidate_set_rel_addr(candidate, "192.168.1.10"));
+    CHECK(ice_candidate_set_rel_port(candidate, 0));
+    CHECK(ice_candidate_set_extension(candidate, (const uint8_t *)"generation", 10, (const uint8_t *)"1", 1));
+    CHECK(ice_candidate_set_extension(candidate, (const uint8_t *)"generation", 10, (const uint8_t *)"0", 1));
+    CHECK(!ice_candidate_set_extension(candidate, (const uint8_t *)"a b", 3, (const uint8_t *)"0", 1));
+
+    // Replacing strings must free the previous ones
+    CHECK(ice_candidate_set_foundation(candidate, "2"));
+    CHECK(ice_candidate_set_rel_addr(candidate, NULL));
+    CHECK(ice_candidate_set_rel_addr(candidate, "192.168.1.11"));
+
+    char *sdp = serialize_ice_candidate(candidate);
+    CHECK(sdp != NULL);
+    CHECK(strcmp(sdp, "candidate:2 1 udp 2130706431 10.0.0.17 46154 typ srflx raddr 192.168.1.11 rport 0 generation 0") == 0);
+    free_ice_candidate_sdp(sdp);
+    free_ice_candidate_sdp(NULL);
+    free_ice_candidate(candidate);
+
+    // Values that would inject or shift fields are rejected
+    candidate = new_ice_candidate();
+    CHECK(!ice_candidate_set_foundation(candidate, "1 1 udp"));
+    CHECK(!ice_candidate_set_foundation(candidate, ""));
+    CHECK(!ice_candidate_set_transport(candidate, "udp 1"));
+    CHECK(!ice_candidate_set_connection_address(candidate, "1.2.3.4 5"));
+    CHECK(!ice_candidate_set_connection_address(candidate, "example.com"));
+    CHECK(!ice_candidate_set_candidate_type(candidate, "host generation 5"));
+    CHECK(!ice_candidate_set_candidate_type(candidate, "host raddr 1.2.3.4"));
+    CHECK(!ice_candidate_set_rel_addr(candidate, "1.2.3.4 rport 5"));
+    CHECK(!ice_candidate_set_extension(candidate, (const uint8_t *)"a", 1, (const uint8_t *)"b c", 3));
+    CHECK(!ice_candidate_set_extension(candidate, (const uint8_t *)"a\tb", 3, (const uint8_t *)"c", 1));
+    CHECK(candidate->foundation == NULL && candidate->transport == NULL && candidate->connection_address == NULL);
+    CHECK(candidate->candidate_type == NULL && candidate->rel_addr == NULL && !candidate->has_extensions);
+    CHECK(ice_candidate_set_foundation(candidate, "1"));
+    CHECK(ice_candidate_set_transport(candidate, "udp"));
+    CHECK(ice_candidate_set_connection_address(candidate, "::1"));
+    CHECK(ice_candidate_set_candidate_type(candidate, "host"));
+    candidate->priority = 12345678901;
+    CHECK(serialize_ice_candidate(candidate) == NULL);
+    CHECK(strcmp(candidateparser_last_error(), "Invalid priority") == 0);
+    candidate->priority = 1;
+    sdp = serialize_ice_candidate(candidate);
+    CHECK(sdp != NULL);
+    CHECK(strcmp(sdp, "candidate:1 0 udp 1 ::1 0 typ host") == 0);
+    free_ice_candidate_sdp(sdp);
+    free_ice_candidate(candidate);
+
+    // Modify a parsed candidate
+    candidate = (IceCandidateFFI *)parse_ice_candidate_sdp(FULL);
+    CHECK(ice_candidate_clear_rel_port(candidate));
+    CHECK(ice_candidate_set_extension(candidate, (const uint8_t *)"ufrag", 5, (const uint8_t *)"abcd", 4));
+    sdp = serialize_ice_candidate(candidate);
+    CHECK(sdp != NULL);
+    CHECK(strcmp(sdp, "candidate:842163049 1 udp 1686052607 1.2.3.4 46154 typ srflx raddr 10.0.0.17 "
+                      "generation 0 network-cost 10 network-id 3 ufrag abcd") == 0);
+    free_ice_candidate_sdp(sdp);
+    free_ice_candidate(candidate);
+}
+
+static void test_key_value_map(void) {
+    const IceCandidateFFI *candidate = parse_ice_candidate_sdp(FULL);
+    const KeyValueMap *map = &candidate->extensions;
+    CHECK(key_value_map_count(map) == 4);
+
+    const uint8_t *val;
+    size_t val_len;
+    CHECK(key_value_map_get(map, (const uint8_t *)"ufrag", 5, &val, &val_len));
+    CHECK(val_len == 4 && memcmp(val, "EEtu", 4) == 0);
+    CHECK(!key_value_map_get(map, (const uint8_t *)"foo", 3, &val, &val_len));
+
+    KeyValuePair pair;
+    for (size_t i = 0; i < key_value_map_count(map); i++) {
+        CHECK(key_value_map_get_index(map, i, &pair));
+        CHECK(pair.key_len > 0 && pair.val_len > 0);
+    }
+    CHECK(!key_value_map_get_index(map, 4, &pair));
+    CHECK(key_value_map_count(NULL) == 0);
+    free_ice_candidate(candidate);
+}
+
+static void test_parse_sdp_candidates(void) {
+    const char *sdp = "v=0\r\n"
+                      "m=audio 9 UDP/TLS/RTP/SAVPF 111\r\n"
+                      "a=mid:0\r\n"
+                      "a=candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host generation 0\r\n"
+                      "a=candidate:2 1 udp 1686052607 1.2.3.4 x typ srflx\r\n"
+                      "m=video 9 UDP/TLS/RTP/SAVPF 96\r\n"
+                      "a=mid:1\r\n"
+                      "a=candidate:3 1 udp 41885439 5.148.189.205 63293 typ relay raddr 1.2.3.4 rport 1\r\n";
+    const SdpCandidateList *list = parse_sdp_candidates(sdp);
+    CHECK(list != NULL);
+    CHECK(list->len == 3);
+    CHECK(list->entries[0].status == PARSE_STATUS_OK);
+    CHECK(list->entries[0].m_line_index == 0);
+    CHECK(strcmp(list->entries[0].mid, "0") == 0);
+    CHECK(list->entries[1].status == PARSE_STATUS_PARSE_ERROR);
+    CHECK(list->entries[1].candidate == NULL);
+    CHECK(strcmp(list->entries[1].error, "Invalid port at offset 39") == 0);
+    CHECK(list->entries[2].status == PARSE_STATUS_OK);
+    CHECK(list->entries[2].has_m_line_index && list->entries[2].m_line_index == 1);
+    CHECK(list->entries[2].line == 8);
+    free_sdp_candidate_list(list);
+
+    list = parse_sdp_candidates_bytes((const uint8_t *)sdp, 0);
+    CHECK(list != NULL && list->len == 0);
+    free_sdp_candidate_list(list);
+    CHECK(parse_sdp_candidates(NULL) == NULL);
+    free_sdp_candidate_list(NULL);
+}
+
+int main(int argc, char *argv[]) {
+    test_version();
+    test_parse();
+    test_try_parse();
+    test_parse_bytes();
+    test_build_and_serialize();
+    test_key_value_map();
+    test_parse_sdp_candidates();
+
+    if (argc > 1 && strcmp(argv[1], "leak") == 0) {
+        const IceCandidateFFI *leaked = parse_ice_candidate_sdp(FULL);
+        CHECK(leaked != NULL);
+    }
+
+    printf("All checks passed\n");
+    return 0;
+}
@@ -0,0 +1,124 @@
+//! Compile the C test harness in `tests/c/harness.c` against the generated
+//! header and the static library, and run it with AddressSanitizer and
+//! LeakSanitizer enabled.
+#![cfg(target_os = "linux")]
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Build the static library and return the directory containing it (e.g.
+/// `target/debug`).
+///
+/// Cargo does not build the `staticlib` crate type for integration tests, so
+/// the library is built explicitly into the same target directory, with the
+/// same profile, target and features as this test.
+fn build_staticlib() -> PathBuf {
+    let exe = env::current_exe().unwrap();
+    let artifacts = exe.parent().and_then(|deps| deps.parent()).unwrap().to_path_buf();
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).parent().unwrap();
+
+    // The artifacts are in `<target-dir>/[<target>/]<profile-dir>`
+    let mut components: Vec<String> = artifacts.strip_prefix(target_dir).unwrap()
+        .iter()
+        .map(|c| c.to_string_lossy().into_owned())
+        .collect();
+    let profile = match components.pop().unwrap().as_str() {
+        "debug" => "dev".to_string(),
+        other => other.to_string(),
+    };
+
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".into());
+    let mut command = Command::new(cargo);
+    command.args(["build", "--lib", "--no-default-features"])
+        .arg("--profile").arg(profile)
+        .arg("--target-dir").arg(target_dir)
+        .current_dir(env!("CARGO_MANIFEST_DIR"));
+    if let Some(target) = components.pop() {
+        command.arg("--target").arg(target);
+    }
+    if cfg!(feature = "clippy") {
+        command.args(["--features", "clippy"]);
+    }
+    let status = command.status().unwrap();
+    assert!(status.success(), "Building the static library failed");
+    artifacts
+}
+
+/// Return the C compiler to use.
+fn cc() -> String {
+    env::var("CC").unwrap_or_else(|_| "cc".into())
+}
+
+/// Return whether the C compiler supports AddressSanitizer and
+/// LeakSanitizer, by compiling and running an empty program.
+fn sanitizers_available() -> bool {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("c_harness_probe");
+    fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("probe.c");
+    let binary = dir.join("probe");
+    fs::write(&source, "int main(void) { return 0; }\n").unwrap();
+    let compiled = Command::new(cc())
+        .arg("-fsanitize=address")
+        .arg(&source)
+        .arg("-o").arg(&binary)
+        .output()
+        .is_ok_and(|output| output.status.success());
+    compiled && Command::new(&binary)
+        .env("ASAN_OPTIONS", "detect_leaks=1")
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// Compile the harness and run it with the specified arguments.
+///
+/// Return `None` if the C compiler does not support the sanitizers.
+fn run_harness(name: &str, args: &[&str]) -> Option<Output> {
+    if !sanitizers_available() {
+        eprintln!("Skipping the C harness: {} does not support -fsanitize=address (set CC to change the C compiler)", cc());
+        return None;
+    }
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let artifacts = build_staticlib();
+    let binary = artifacts.join(name);
+    let cc = cc();
+
+    let status = Command::new(&cc)
+        .args(["-std=c99", "-Wall", "-Wextra", "-Werror", "-pedantic-errors", "-g"])
+        .args(["-fsanitize=address", "-fno-omit-frame-pointer"])
+        .arg("-I").arg(&manifest_dir)
+        .arg(manifest_dir.join("tests").join("c").join("harness.c"))
+        .arg(artifacts.join("libcandidateparser_ffi.a"))
+        .args(["-lpthread", "-ldl", "-lm"])
+        .arg("-o").arg(&binary)
+        .status()
+        .unwrap_or_else(|e| panic!("Could not run C compiler {}: {}", cc, e));
+    assert!(status.success(), "Compiling the C harness failed");
+
+    Some(Command::new(&binary)
+        .args(args)
+        .env("ASAN_OPTIONS", "detect_leaks=1")
+        .output()
+        .unwrap())
+}
+
+#[test]
+fn c_harness() {
+    let output = match run_harness("c_harness", &[]) {
+        Some(output) => output,
+        None => return,
+    };
+    assert!(output.status.success(), "C harness failed:\n{}{}",
+            String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn c_harness_detects_leaks() {
+    let output = match run_harness("c_harness_leak", &["leak"]) {
+        Some(output) => output,
+        None => return,
+    };
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("LeakSanitizer: detected memory leaks"));
+}
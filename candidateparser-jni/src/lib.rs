@@ -7,28 +7,124 @@ extern crate candidateparser;
 extern crate jni;
 #[macro_use] extern crate log;
 
+use std::convert::TryFrom;
 use std::ptr;
 
-use candidateparser::IceCandidate;
+use candidateparser::{IceCandidate, ExtensionMap, Field, LineError, ParseError, SdpCandidate};
 use jni::JNIEnv;
-use jni::errors::{Result as JniResult};
-use jni::objects::{JClass, JString, JValue};
-use jni::sys::{jobject, _jobject, jlong, jint};
-
-/// Unwrap the object. If unwrapping fails, print the error message to the
-/// Android logcat before panicking.
-macro_rules! jni_unwrap {
-    ($obj:expr, $msg:expr) => {
-        $obj.unwrap_or_else(|e| {
-            error!("Error: {}: {}", $msg, e);
-            panic!($msg);
-        });
-    };
+use jni::errors::{Error as JniError, ErrorKind, Result as JniResult};
+use jni::objects::{JClass, JObject, JString, JThrowable, JValue};
+use jni::sys::{jobject, jstring, jlong, jint};
+
+/// An error that is converted into a Java exception.
+enum Error {
+    /// The candidate could not be parsed.
+    Parse(ParseError),
+    /// A field of a Java object is invalid.
+    Field(Field),
+    /// A parameter or a required field of a Java object is `null`.
+    Null(&'static str),
+    /// A JNI call failed.
+    Jni(JniError),
+}
+
+impl From<JniError> for Error {
+    fn from(e: JniError) -> Self {
+        Error::Jni(e)
+    }
 }
 
 /// Create a new `null` jobject.
 fn null_jobject() -> jobject {
-    ptr::null_mut() as *mut _jobject
+    ptr::null_mut()
+}
+
+/// Throw the error as Java exception.
+///
+/// Parse errors are thrown as `CandidateParseException`. JNI errors are
+/// thrown as `RuntimeException`, unless a Java exception is already pending
+/// (e.g. because a Java method threw), in which case that one is propagated.
+fn throw(env: &JNIEnv, error: Error) {
+    let result = match error {
+        Error::Parse(e) => parse_exception_to_java(env, &e, None)
+            .and_then(|exception| env.throw(JThrowable::from(exception))),
+        Error::Field(field) => field_exception_to_java(env, field)
+            .and_then(|exception| env.throw(JThrowable::from(exception))),
+        Error::Null(field) => env.throw_new("java/lang/NullPointerException", format!("Missing {}", field)),
+        Error::Jni(e) => {
+            error!("Error: JNI call failed: {}", e);
+            match env.exception_check() {
+                Ok(true) => Ok(()),
+                _ => env.throw_new("java/lang/RuntimeException", format!("JNI call failed: {}", e)),
+            }
+        },
+    };
+    if let Err(e) = result {
+        error!("Error: Could not throw exception: {}", e);
+    }
+}
+
+/// Create a `CandidateParseException` Java object for the parse error. If the
+/// error occurred while parsing a session description, the line is included.
+fn parse_exception_to_java<'a>(env: &'a JNIEnv, error: &ParseError, line: Option<LineError>) -> JniResult<JObject<'a>> {
+    let message = JValue::Object(env.new_string(match line {
+        Some(e) => e.to_string(),
+        None => error.to_string(),
+    })?.into());
+    let field = JValue::Object(env.new_string(error.field.to_string())?.into());
+    let offset = JValue::Int(error.offset as jint);
+    match line {
+        Some(e) => env.new_object(
+            "ch/dbrgn/candidateparser/CandidateParseException",
+            "(Ljava/lang/String;Ljava/lang/String;II)V",
+            &[message, field, offset, JValue::Int(e.line as jint)]
+        ),
+        None => env.new_object(
+            "ch/dbrgn/candidateparser/CandidateParseException",
+            "(Ljava/lang/String;Ljava/lang/String;I)V",
+            &[message, field, offset]
+        ),
+    }
+}
+
+/// Create a `CandidateParseException` Java object for an invalid field of a
+/// Java object. It does not contain an offset, since there is no input
+/// string.
+fn field_exception_to_java<'a>(env: &'a JNIEnv, field: Field) -> JniResult<JObject<'a>> {
+    let message = JValue::Object(env.new_string(format!("Invalid {}", field))?.into());
+    let field = JValue::Object(env.new_string(field.to_string())?.into());
+    env.new_object(
+        "ch/dbrgn/candidateparser/CandidateParseException",
+        "(Ljava/lang/String;Ljava/lang/String;)V",
+        &[message, field]
+    )
+}
+
+/// Run `f` in a new local reference frame. All local references created by
+/// `f` are released, except for the returned object.
+///
+/// This prevents overflowing the local reference table when creating many
+/// objects in a single native call.
+fn with_local_frame<'a, F>(env: &JNIEnv<'a>, f: F) -> Result<JObject<'a>, Error>
+    where F: FnOnce() -> Result<JObject<'a>, Error>
+{
+    let raw = env.get_native_interface();
+    unsafe {
+        let push_local_frame = (**raw).PushLocalFrame
+            .ok_or_else(|| JniError::from(ErrorKind::JNIEnvMethodNotFound("PushLocalFrame")))?;
+        let pop_local_frame = (**raw).PopLocalFrame
+            .ok_or_else(|| JniError::from(ErrorKind::JNIEnvMethodNotFound("PopLocalFrame")))?;
+        if push_local_frame(raw, 16) < 0 {
+            return Err(Error::Jni(ErrorKind::JavaException.into()));
+        }
+        match f() {
+            Ok(obj) => Ok(JObject::from(pop_local_frame(raw, obj.into_inner()))),
+            Err(e) => {
+                pop_local_frame(raw, ptr::null_mut());
+                Err(e)
+            },
+        }
+    }
 }
 
 /// Build a vector of constructor arguments for the `IceCandidate` constructor.
@@ -51,7 +147,178 @@ fn ice_candidate_ctor_args<'a>(env: &'a JNIEnv, candidate: &IceCandidate) -> Jni
     ])
 }
 
+/// Create an `IceCandidate` Java object from the candidate.
+fn ice_candidate_to_java<'a>(env: &'a JNIEnv, candidate: IceCandidate) -> JniResult<JObject<'a>> {
+    // Create list of constructor parameters
+    let args = ice_candidate_ctor_args(env, &candidate)?;
+
+    // Create IceCandidate Java object
+    let obj = env.new_object(
+        "ch/dbrgn/candidateparser/IceCandidate",
+        "(Ljava/lang/String;JLjava/lang/String;JLjava/lang/String;ILjava/lang/String;)V",
+        &args
+    )?;
+
+    // Set optional fields
+    if let Some(ip) = candidate.rel_addr {
+        let ip_jni_string = env.new_string(ip.to_string())?;
+        env.call_method(obj, "setRelAddr", "(Ljava/lang/String;)V",
+                        &[JValue::Object(ip_jni_string.into())])?;
+    }
+    if let Some(port) = candidate.rel_port {
+        env.call_method(obj, "setRelPort", "(I)V", &[JValue::Int(port as i32)])?;
+    }
+    if let Some(extensions) = candidate.extensions {
+        // The extensions are passed as raw byte arrays, sorted by key to get
+        // a deterministic order.
+        let mut extensions: Vec<_> = extensions.into_iter().collect();
+        extensions.sort();
+        for (k, v) in extensions {
+            let key_array = JObject::from(env.byte_array_from_slice(&k)?);
+            let val_array = JObject::from(env.byte_array_from_slice(&v)?);
+            env.call_method(obj, "addExtensionBytes", "([B[B)V",
+                            &[JValue::Object(key_array), JValue::Object(val_array)])?;
+        }
+    }
+
+    Ok(obj)
+}
+
+/// Call a getter returning an object. Return `None` if the value is `null`.
+fn get_object<'a>(env: &'a JNIEnv, obj: JObject, getter: &str, sig: &str) -> JniResult<Option<JObject<'a>>> {
+    // A `null` result is reported as `NullPtr` error by the jni crate
+    match env.call_method(obj, getter, sig, &[]) {
+        Ok(value) => value.l().map(Some),
+        Err(JniError(ErrorKind::NullPtr(_), _)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Call a getter returning a string. Return `None` if the value is `null`.
+fn get_string(env: &JNIEnv, obj: JObject, getter: &str) -> JniResult<Option<String>> {
+    match get_object(env, obj, getter, "()Ljava/lang/String;")? {
+        Some(value) => Ok(Some(env.get_string(JString::from(value))?.into())),
+        None => Ok(None),
+    }
+}
+
+/// Call a getter returning a required string.
+fn get_required_string(env: &JNIEnv, obj: JObject, getter: &str, field: &'static str) -> Result<String, Error> {
+    get_string(env, obj, getter)?.ok_or(Error::Null(field))
+}
+
+/// Convert a Java string parameter to a Rust string. A `null` parameter is
+/// reported as missing.
+fn string_param(env: &JNIEnv, input: JString, name: &'static str) -> Result<String, Error> {
+    if input.is_null() {
+        return Err(Error::Null(name));
+    }
+    Ok(env.get_string(input)?.into())
+}
+
+/// Convert a Java integer to the type of the field.
+fn convert_int<T, U>(value: U, field: Field) -> Result<T, Error> where T: TryFrom<U> {
+    T::try_from(value).map_err(|_| Error::Field(field))
+}
+
+/// Create an `IceCandidate` from the fields of an `IceCandidate` Java object.
+///
+/// Every field is validated on its own, so that no value can add or shift
+/// fields when the candidate is serialized.
+fn ice_candidate_from_java(env: &JNIEnv, obj: JObject) -> Result<IceCandidate, Error> {
+    if obj.is_null() {
+        return Err(Error::Null("candidate"));
+    }
+    let invalid = |e: ParseError| Error::Field(e.field);
+
+    let foundation = get_required_string(env, obj, "getFoundation", "foundation")?;
+    let component_id = env.call_method(obj, "getComponentId", "()J", &[])?.j()?;
+    let transport = get_required_string(env, obj, "getTransport", "transport")?;
+    let priority = env.call_method(obj, "getPriority", "()J", &[])?.j()?;
+    let connection_address = get_required_string(env, obj, "getConnectionAddress", "connection-address")?;
+    let port = env.call_method(obj, "getPort", "()I", &[])?.i()?;
+    let candidate_type = get_required_string(env, obj, "getCandidateType", "cand-type")?;
+    let rel_addr = match get_string(env, obj, "getRelAddr")? {
+        Some(addr) => Some(candidateparser::parse_address(Field::RelAddr, addr.as_bytes()).map_err(invalid)?),
+        None => None,
+    };
+    let rel_port = match get_object(env, obj, "getRelPort", "()Ljava/lang/Integer;")? {
+        Some(port) => Some(convert_int(env.call_method(port, "intValue", "()I", &[])?.i()?, Field::RelPort)?),
+        None => None,
+    };
+    let mut extensions = ExtensionMap::new();
+    let list = env.call_method(obj, "getExtensionList", "()Ljava/util/List;", &[])?.l()?;
+    let len = env.call_method(list, "size", "()I", &[])?.i()?;
+    for i in 0..len {
+        let extension = env.call_method(list, "get", "(I)Ljava/lang/Object;", &[JValue::Int(i)])?.l()?;
+        let key = env.call_method(extension, "getKey", "()[B", &[])?.l()?;
+        let val = env.call_method(extension, "getValue", "()[B", &[])?.l()?;
+        extensions.insert(env.convert_byte_array(key.into_inner())?, env.convert_byte_array(val.into_inner())?);
+    }
+
+    let candidate = IceCandidate {
+        foundation: candidateparser::parse_foundation(foundation.as_bytes()).map_err(invalid)?,
+        component_id: convert_int(component_id, Field::ComponentId)?,
+        transport: candidateparser::parse_transport(transport.as_bytes()).map_err(invalid)?,
+        priority: convert_int(priority, Field::Priority)?,
+        connection_address: candidateparser::parse_address(Field::ConnectionAddress, connection_address.as_bytes())
+            .map_err(invalid)?,
+        port: convert_int(port, Field::Port)?,
+        candidate_type: candidateparser::parse_candidate_type(candidate_type.as_bytes()).map_err(invalid)?,
+        rel_addr,
+        rel_port,
+        extensions: if extensions.is_empty() { None } else { Some(extensions) },
+    };
+    candidate.validate().map_err(invalid)?;
+    Ok(candidate)
+}
+
+/// Parse the Java string and create an `IceCandidate` Java object.
+fn parse_sdp<'a>(env: &'a JNIEnv, input: JString) -> Result<JObject<'a>, Error> {
+    // Convert parameter Java string to Rust string
+    let sdp = string_param(env, input, "sdp")?;
+
+    // Parse SDP
+    let candidate = candidateparser::try_parse(sdp.as_bytes()).map_err(Error::Parse)?;
+
+    Ok(ice_candidate_to_java(env, candidate)?)
+}
+
+/// Parse all candidates in the session description and create a
+/// `java.util.List` of `IceCandidate` Java objects. The parse errors of
+/// invalid candidates are added to the `errors` list, unless it is null.
+fn parse_session_description<'a>(env: &'a JNIEnv, input: JString, errors: JObject) -> Result<JObject<'a>, Error> {
+    // Convert parameter Java string to Rust string
+    let sdp = string_param(env, input, "sdp")?;
+
+    let list = env.new_object("java/util/ArrayList", "()V", &[])?;
+    for SdpCandidate { line, m_line_index, mid, result } in candidateparser::parse_sdp(sdp.as_bytes()) {
+        with_local_frame(env, || {
+            let (target, obj) = match result {
+                Ok(candidate) => {
+                    let obj = ice_candidate_to_java(env, candidate)?;
+                    if let Some(mid) = mid {
+                        let mid = JObject::from(env.new_string(mid)?);
+                        env.call_method(obj, "setSdpMid", "(Ljava/lang/String;)V", &[JValue::Object(mid)])?;
+                    }
+                    if let Some(index) = m_line_index {
+                        env.call_method(obj, "setSdpMLineIndex", "(I)V", &[JValue::Int(index as jint)])?;
+                    }
+                    (list, obj)
+                },
+                Err(_) if errors.is_null() => return Ok(JObject::null()),
+                Err(error) => (errors, parse_exception_to_java(env, &error, Some(LineError { line, error }))?),
+            };
+            env.call_method(target, "add", "(Ljava/lang/Object;)Z", &[JValue::Object(obj)])?;
+            Ok(JObject::null())
+        })?;
+    }
+    Ok(list)
+}
+
 /// JNI bindings for `parseSdp` method in `ch.dbrgn.candidateparser.CandidateParser`.
+///
+/// If parsing fails, a `CandidateParseException` is thrown.
 #[no_mangle]
 #[allow(non_snake_case)]
 pub extern "system"
@@ -62,65 +329,65 @@ fn Java_ch_dbrgn_candidateparser_CandidateParser_parseSdp(env: JNIEnv,
     #[cfg(target_os = "android")]
     android_logger::init_once(log::LogLevel::Info);
 
-    // Convert parameter Java string to Rust string
-    let sdp: String = jni_unwrap!(env.get_string(input),
-                                  "Couldn't get java string").into();
+    match parse_sdp(&env, input) {
+        Ok(obj) => obj.into_inner(),
+        Err(e) => {
+            throw(&env, e);
+            null_jobject()
+        },
+    }
+}
 
-    // Parse SDP
-    let candidate = match candidateparser::parse(sdp.as_bytes()) {
-        Some(cand) => cand,
-        None => return null_jobject(),
-    };
+/// JNI bindings for `parseSessionDescription` method in
+/// `ch.dbrgn.candidateparser.CandidateParser`.
+///
+/// The `CandidateParseException`s of invalid candidates are added to the
+/// `errors` list instead of being thrown, so they don't prevent the other
+/// candidates from being returned.
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system"
+fn Java_ch_dbrgn_candidateparser_CandidateParser_parseSessionDescription(env: JNIEnv,
+                                                                         _class: JClass,
+                                                                         input: JString,
+                                                                         errors: JObject)
+                                                                         -> jobject {
+    #[cfg(target_os = "android")]
+    android_logger::init_once(log::LogLevel::Info);
 
-    // Create list of constructor parameters
-    let args = match ice_candidate_ctor_args(&env, &candidate) {
-        Ok(args) => args,
+    match parse_session_description(&env, input, errors) {
+        Ok(list) => list.into_inner(),
         Err(e) => {
-            error!("Error: Could not build constructor args: {}", e);
-            return null_jobject();
+            throw(&env, e);
+            null_jobject()
         },
-    };
+    }
+}
 
-    // Create IceCandidate Java object
-    let obj = jni_unwrap!(
-        env.new_object(
-            "ch/dbrgn/candidateparser/IceCandidate",
-            "(Ljava/lang/String;JLjava/lang/String;JLjava/lang/String;ILjava/lang/String;)V",
-            &args
-        ), "Could not create new IceCandidate instance"
-    );
+/// JNI bindings for `toSdp` method in `ch.dbrgn.candidateparser.CandidateParser`.
+///
+/// If a field of the candidate is invalid, a `CandidateParseException` without
+/// offset is thrown.
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system"
+fn Java_ch_dbrgn_candidateparser_CandidateParser_toSdp(env: JNIEnv,
+                                                       _class: JClass,
+                                                       candidate: JObject)
+                                                       -> jstring {
+    #[cfg(target_os = "android")]
+    android_logger::init_once(log::LogLevel::Info);
 
-    // Set optional fields
-    if let Some(ip) = candidate.rel_addr {
-        let ip_jni_string = jni_unwrap!(env.new_string(ip.to_string()), "Could not create JNIString from rel_addr");
-        if let Err(e) = env.call_method(obj, "setRelAddr", "(Ljava/lang/String;)V",
-                                        &[JValue::Object(ip_jni_string.into())]) {
-            error!("Error: Could not call setRelAddr: {}", e);
-            return null_jobject();
-        }
-    }
-    if let Some(port) = candidate.rel_port {
-        if let Err(e) = env.call_method(obj, "setRelPort", "(I)V", &[JValue::Int(port as i32)]) {
-            error!("Error: Could not call setRelPort: {}", e);
-            return null_jobject();
-        }
-    }
-    if let Some(extensions) = candidate.extensions {
-        for (k, v) in extensions {
-            // Note: In theory the extension fields are byte arrays, not strings.
-            // But since Java cannot use byte[] as map key, and because the Java
-            // libwebrtc bindings return a String for the candidate SDP anyways,
-            // we'll use strings and assume that they're valid UTF8.
-            let key_jni_string = jni_unwrap!(env.new_string(String::from_utf8_lossy(&k)), "Could not create JNIString from extension key");
-            let val_jni_string = jni_unwrap!(env.new_string(String::from_utf8_lossy(&v)), "Could not create JNIString from extension val");
-            if let Err(e) = env.call_method(obj, "addExtension", "(Ljava/lang/String;Ljava/lang/String;)V",
-                                            &[JValue::Object(key_jni_string.into()), JValue::Object(val_jni_string.into())]) {
-                error!("Error: Could not call addExtension: {}", e);
-                return null_jobject();
-            }
-        }
+    let result = ice_candidate_from_java(&env, candidate).and_then(|candidate| {
+        // Note: Extensions that are not valid UTF8 are converted lossily,
+        // since the result is a Java string.
+        Ok(env.new_string(String::from_utf8_lossy(&candidate.to_sdp()))?)
+    });
+    match result {
+        Ok(sdp) => sdp.into_inner(),
+        Err(e) => {
+            throw(&env, e);
+            ptr::null_mut()
+        },
     }
-
-    // Return instance
-    obj.into_inner()
 }
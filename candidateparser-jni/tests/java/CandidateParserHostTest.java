@@ -0,0 +1,8 @@
+package ch.dbrgn.candidateparser;
+
+/**
+ * Runs the tests of the Android library (CandidateParserTestBase) in a
+ * desktop JVM.
+ */
+public class CandidateParserHostTest extends CandidateParserTestBase {
+}
@@ -0,0 +1,143 @@
+//! Compile the Java sources of the Android library together with its tests,
+//! and run them with JUnit in a desktop JVM that loads the bindings.
+//!
+//! The tests are skipped if no JDK or no JUnit is available, unless
+//! `CANDIDATEPARSER_REQUIRE_JVM_TESTS` is set.
+#![cfg(target_os = "linux")]
+
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The JUnit jar installed by the Debian `junit4` package. It references
+/// Hamcrest in its manifest.
+const DEBIAN_JUNIT: &str = "/usr/share/java/junit4.jar";
+
+/// Build the shared library and return the directory containing it (e.g.
+/// `target/debug`).
+///
+/// Cargo does not build the `cdylib` crate type for integration tests, so
+/// the library is built explicitly into the same target directory, with the
+/// same profile and target as this test.
+fn build_cdylib() -> PathBuf {
+    let exe = env::current_exe().unwrap();
+    let artifacts = exe.parent().and_then(|deps| deps.parent()).unwrap().to_path_buf();
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).parent().unwrap();
+
+    // The artifacts are in `<target-dir>/[<target>/]<profile-dir>`
+    let mut components: Vec<String> = artifacts.strip_prefix(target_dir).unwrap()
+        .iter()
+        .map(|c| c.to_string_lossy().into_owned())
+        .collect();
+    let profile = match components.pop().unwrap().as_str() {
+        "debug" => "dev".to_string(),
+        other => other.to_string(),
+    };
+
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".into());
+    let mut command = Command::new(cargo);
+    command.args(["build", "--lib"])
+        .arg("--profile").arg(profile)
+        .arg("--target-dir").arg(target_dir)
+        .current_dir(env!("CARGO_MANIFEST_DIR"));
+    if let Some(target) = components.pop() {
+        command.arg("--target").arg(target);
+    }
+    let status = command.status().unwrap();
+    assert!(status.success(), "Building the shared library failed");
+    artifacts
+}
+
+/// Return all Java source files in the directory.
+fn java_sources(dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(dir).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "java"))
+        .collect()
+}
+
+/// Return the path of a JDK tool, using `JAVA_HOME` if it is set.
+fn jdk_tool(name: &str) -> PathBuf {
+    match env::var_os("JAVA_HOME") {
+        Some(home) => PathBuf::from(home).join("bin").join(name),
+        None => PathBuf::from(name),
+    }
+}
+
+/// Return whether the JDK tool can be run.
+fn jdk_tool_available(name: &str) -> bool {
+    Command::new(jdk_tool(name))
+        .arg("-version")
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// Return the class path of JUnit 4, using `JUNIT_CLASSPATH` if it is set.
+fn junit_classpath() -> Option<OsString> {
+    env::var_os("JUNIT_CLASSPATH")
+        .or_else(|| Some(OsString::from(DEBIAN_JUNIT)).filter(|_| Path::new(DEBIAN_JUNIT).exists()))
+}
+
+/// Report that the tests are skipped, or fail if they are required.
+///
+/// The notice is written to stderr directly, since the test harness captures
+/// the output of passing tests.
+#[allow(clippy::explicit_write)]
+fn skip(reason: &str) {
+    assert!(env::var_os("CANDIDATEPARSER_REQUIRE_JVM_TESTS").is_none(),
+            "The JVM tests are required, but {}", reason);
+    writeln!(io::stderr(), "Skipping the JVM tests: {}", reason).unwrap();
+}
+
+#[test]
+fn jvm() {
+    if !jdk_tool_available("javac") || !jdk_tool_available("java") {
+        skip("javac or java not found (set JAVA_HOME to use another JDK)");
+        return;
+    }
+    let junit = match junit_classpath() {
+        Some(junit) => junit,
+        None => {
+            skip(&format!("{} not found (set JUNIT_CLASSPATH to the JUnit 4 and Hamcrest jars)", DEBIAN_JUNIT));
+            return;
+        },
+    };
+
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let android_src = manifest_dir.join("../candidateparser-android/app/src");
+    let artifacts = build_cdylib();
+    let classes = artifacts.join("candidateparser-jni-classes");
+    let mut classpath = vec![classes.clone().into_os_string()];
+    classpath.extend(env::split_paths(&junit).map(PathBuf::into_os_string));
+    let classpath = env::join_paths(classpath).unwrap();
+
+    // The Android test itself needs the Android test runner, only the shared
+    // base class is compiled
+    let mut sources = java_sources(&android_src.join("main/java/ch/dbrgn/candidateparser"));
+    sources.push(android_src.join("androidTest/java/ch/dbrgn/candidateparser/CandidateParserTestBase.java"));
+    sources.extend(java_sources(&manifest_dir.join("tests").join("java")));
+    let javac = jdk_tool("javac");
+    let status = Command::new(&javac)
+        .args(["-encoding", "UTF-8", "-Xlint:all,-serial,-path", "-Werror"])
+        .arg("-cp").arg(&classpath)
+        .arg("-d").arg(&classes)
+        .args(&sources)
+        .status()
+        .unwrap_or_else(|e| panic!("Could not run {}: {}", javac.display(), e));
+    assert!(status.success(), "Compiling the Java sources failed");
+
+    let output = Command::new(jdk_tool("java"))
+        .arg("-Xcheck:jni")
+        .arg(format!("-Djava.library.path={}", artifacts.display()))
+        .arg("-cp").arg(&classpath)
+        .args(["org.junit.runner.JUnitCore", "ch.dbrgn.candidateparser.CandidateParserHostTest"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Java tests failed:\n{}{}", stdout, stderr);
+    assert!(!stdout.contains("WARNING") && !stderr.contains("WARNING"), "JNI check failed:\n{}{}", stdout, stderr);
+}
@@ -0,0 +1,27 @@
+//! Benchmark of the hand-written parser against the nom parsers.
+#[macro_use] extern crate criterion;
+extern crate candidateparser;
+
+use criterion::Criterion;
+
+const MINIMAL: &[u8] = b"candidate:373990095 1 udp 41885439 5.148.189.205 63293 typ relay";
+const FULL: &[u8] = b"candidate:842163049 1 udp 1686052607 1.2.3.4 46154 typ srflx raddr 10.0.0.17 rport 46154 generation 0 ufrag EEtu network-id 3 network-cost 10";
+const IPV6: &[u8] = b"candidate:1 1 udp 2122262783 2001:db8:85a3::8a2e:370:7334 58481 typ host generation 0";
+
+fn bench_parse(c: &mut Criterion) {
+    let inputs = [("minimal", MINIMAL), ("full", FULL), ("ipv6", IPV6)];
+    let mut group = c.benchmark_group("parse");
+    for &(name, input) in &inputs {
+        group.bench_function(format!("handwritten/{}", name), |b| {
+            b.iter(|| candidateparser::parse(criterion::black_box(input)))
+        });
+        #[cfg(feature = "parsers")]
+        group.bench_function(format!("nom/{}", name), |b| {
+            b.iter(|| candidateparser::parsers::ice_candidate(criterion::black_box(input)).to_full_result().ok())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse);
+criterion_main!(benches);
@@ -0,0 +1,7 @@
+#![no_main]
+#[macro_use] extern crate libfuzzer_sys;
+extern crate candidateparser;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = candidateparser::parsers::cand_type(data);
+});
@@ -0,0 +1,17 @@
+#![no_main]
+#[macro_use] extern crate libfuzzer_sys;
+extern crate candidateparser;
+
+fuzz_target!(|data: &[u8]| {
+    // The hand-written parser must behave exactly like the nom parsers.
+    let result = candidateparser::parsers::ice_candidate(data);
+    let nom = if result.is_done() {
+        match result.unwrap() {
+            (&[], candidate) => Some(candidate),
+            _ => None,
+        }
+    } else {
+        None
+    };
+    assert_eq!(candidateparser::parse(data), nom);
+});
@@ -0,0 +1,16 @@
+#![no_main]
+#[macro_use] extern crate libfuzzer_sys;
+extern crate candidateparser;
+
+fuzz_target!(|data: &[u8]| {
+    // If parsing succeeds, serializing and parsing again must yield the same
+    // candidate. Extensions named `raddr` or `rport` are skipped, since they
+    // are indistinguishable from the rel-addr and rel-port fields.
+    if let Some(candidate) = candidateparser::parse(data) {
+        if candidate.extension(b"raddr").is_some() || candidate.extension(b"rport").is_some() {
+            return;
+        }
+        let sdp = candidate.to_sdp();
+        assert_eq!(candidateparser::parse(&sdp), Some(candidate));
+    }
+});
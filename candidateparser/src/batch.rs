@@ -0,0 +1,295 @@
+//! Parsing of many candidates at once.
+use core::fmt;
+use core::slice;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::error;
+
+use ::reader::{self, ParseError};
+use ::types::IceCandidate;
+
+/// An error that occurred while parsing a line of a batch.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct LineError {
+    /// The line number, starting at 1.
+    pub line: usize,
+    /// The parse error. The offset is relative to the start of the line.
+    pub error: ParseError,
+}
+
+impl fmt::Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Line {}: {}", self.line, self.error)
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for LineError {}
+
+/// The result of parsing a line: The line number (starting at 1) and the
+/// parsed candidate, or an error.
+pub type LineResult = Result<(usize, IceCandidate), LineError>;
+
+/// An iterator over the parse results of a batch of candidate lines.
+///
+/// Each item contains either the line number (starting at 1) and the parsed
+/// candidate, or a [`LineError`](struct.LineError.html). Empty lines are
+/// skipped, but still counted. Trailing carriage returns and an `a=` prefix
+/// are removed before parsing.
+///
+/// Create it using [`parse_lines`](fn.parse_lines.html) or
+/// [`parse_buffer`](fn.parse_buffer.html).
+#[derive(Debug, Clone)]
+pub struct Batch<I> {
+    lines: I,
+    line: usize,
+    stop_at_first_error: bool,
+    done: bool,
+}
+
+impl<I> Batch<I> {
+    /// Stop iterating after the first error has been returned.
+    pub fn stop_at_first_error(mut self) -> Self {
+        self.stop_at_first_error = true;
+        self
+    }
+}
+
+impl<I, T> Iterator for Batch<I> where I: Iterator<Item=T>, T: AsRef<[u8]> {
+    type Item = LineResult;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        loop {
+            let line = self.lines.next()?;
+            self.line += 1;
+            if let Some(result) = parse_line(self.line, line.as_ref()) {
+                self.done = result.is_err() && self.stop_at_first_error;
+                return Some(result);
+            }
+        }
+    }
+}
+
+/// Parse a single line of a batch. Return `None` if the line is empty.
+pub fn parse_line(line: usize, mut sdp: &[u8]) -> Option<LineResult> {
+    if sdp.last() == Some(&b'\r') {
+        sdp = &sdp[..sdp.len() - 1];
+    }
+    if sdp.is_empty() {
+        return None;
+    }
+    let prefix_len = if sdp.starts_with(b"a=") { 2 } else { 0 };
+    Some(match reader::ice_candidate(&sdp[prefix_len..]) {
+        Ok(candidate) => Ok((line, candidate)),
+        Err(mut error) => {
+            error.offset += prefix_len;
+            Err(LineError { line, error })
+        },
+    })
+}
+
+/// Parse each of the specified lines as a candidate.
+///
+/// ## Example
+///
+/// ```rust
+/// let lines = vec![
+///     "candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host",
+///     "candidate:2 1 udp 1686052607 1.2.3.4 46154 typ srflx raddr 10.0.0.17 rport 46154",
+/// ];
+/// let candidates: Vec<_> = candidateparser::parse_lines(lines).collect();
+/// assert_eq!(candidates.len(), 2);
+/// ```
+pub fn parse_lines<I, T>(lines: I) -> Batch<I::IntoIter> where I: IntoIterator<Item=T>, T: AsRef<[u8]> {
+    Batch {
+        lines: lines.into_iter(),
+        line: 0,
+        stop_at_first_error: false,
+        done: false,
+    }
+}
+
+/// The lines of a buffer, split at LF.
+type Lines<'a> = slice::Split<'a, u8, fn(&u8) -> bool>;
+
+/// Parse a newline separated buffer of candidates.
+///
+/// Lines may be terminated by LF or CRLF.
+pub fn parse_buffer(buf: &[u8]) -> Batch<Lines<'_>> {
+    parse_lines(buf.split(is_newline as fn(&u8) -> bool))
+}
+
+fn is_newline(c: &u8) -> bool {
+    *c == b'\n'
+}
+
+/// A candidate found in a session description.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SdpCandidate {
+    /// The line number, starting at 1.
+    pub line: usize,
+    /// The index of the media description (`m=` line) containing the
+    /// candidate, starting at 0. `None` if the candidate precedes the first
+    /// media description.
+    pub m_line_index: Option<usize>,
+    /// The identification tag (`a=mid:`) of the media description.
+    pub mid: Option<String>,
+    /// The parsed candidate, or the error. The offset is relative to the start
+    /// of the line.
+    pub result: Result<IceCandidate, ParseError>,
+}
+
+/// Parse all candidates in a session description.
+///
+/// Only `a=candidate:` lines (or `candidate:` lines, so that plain candidate
+/// lists can be passed in as well) are parsed, all other lines are used to
+/// determine the media description of each candidate.
+///
+/// ## Example
+///
+/// ```rust
+/// let sdp = b"v=0\r\n\
+///             m=audio 9 UDP/TLS/RTP/SAVPF 111\r\n\
+///             a=mid:audio\r\n\
+///             a=candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host\r\n";
+/// let candidates = candidateparser::parse_sdp(sdp);
+/// assert_eq!(candidates.len(), 1);
+/// assert_eq!(candidates[0].m_line_index, Some(0));
+/// assert_eq!(candidates[0].mid, Some("audio".to_string()));
+/// ```
+pub fn parse_sdp(sdp: &[u8]) -> Vec<SdpCandidate> {
+    let mut candidates: Vec<SdpCandidate> = vec![];
+    let mut m_line_index = None;
+    let mut mid = None;
+    let mut section_start = 0;
+    for (i, line) in sdp.split(is_newline).enumerate() {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if line.starts_with(b"m=") {
+            set_mid(&mut candidates[section_start..], mid.take());
+            section_start = candidates.len();
+            m_line_index = Some(m_line_index.map_or(0, |index| index + 1));
+        } else if let Some(val) = line.strip_prefix(b"a=mid:") {
+            mid = Some(String::from_utf8_lossy(val).into_owned());
+        } else if line.starts_with(b"a=candidate:") || line.starts_with(b"candidate:") {
+            if let Some(result) = parse_line(i + 1, line) {
+                candidates.push(SdpCandidate {
+                    line: i + 1,
+                    m_line_index,
+                    mid: None,
+                    result: result.map(|(_, candidate)| candidate).map_err(|e| e.error),
+                });
+            }
+        }
+    }
+    set_mid(&mut candidates[section_start..], mid);
+    candidates
+}
+
+/// Set the mid of all candidates in a media description.
+fn set_mid(candidates: &mut [SdpCandidate], mid: Option<String>) {
+    for candidate in candidates {
+        candidate.mid = mid.clone();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use ::reader::Field;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_lines() {
+        let lines = vec![
+            "candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host",
+            "candidate:2 1 udp 1686052607 1.2.3.4 46154 typ srflx raddr 10.0.0.17 rport 46154",
+        ];
+        let results: Vec<_> = parse_lines(&lines).collect();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].as_ref().unwrap().0, 1);
+        assert_eq!(results[0].as_ref().unwrap().1.foundation, "1");
+        assert_eq!(results[1].as_ref().unwrap().0, 2);
+        assert_eq!(results[1].as_ref().unwrap().1.foundation, "2");
+    }
+
+    #[test]
+    fn test_parse_buffer() {
+        let buf = b"a=candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host\r\n\
+                    \r\n\
+                    a=candidate:2 1 udp 1686052607 1.2.3.4 x typ srflx\r\n\
+                    candidate:3 1 udp 41885439 5.148.189.205 63293 typ relay\n";
+        let results: Vec<_> = parse_buffer(buf).collect();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().0, 1);
+        assert_eq!(results[1], Err(LineError {
+            line: 3,
+            error: ParseError { field: Field::Port, offset: 39 },
+        }));
+        assert_eq!(results[2].as_ref().unwrap().0, 4);
+        assert_eq!(results[2].as_ref().unwrap().1.foundation, "3");
+    }
+
+    #[test]
+    fn test_stop_at_first_error() {
+        let lines = ["candidate:1 1 udp 1 1.2.3.4 5 typ host", "foo", "bar"];
+        let results: Vec<_> = parse_lines(&lines).stop_at_first_error().collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert_eq!(results[1], Err(LineError { line: 2, error: ParseError { field: Field::Prefix, offset: 0 } }));
+
+        let results: Vec<_> = parse_lines(&lines).collect();
+        assert_eq!(results.len(), 3);
+    }
+
+    #[test]
+    fn test_line_error_display() {
+        let error = LineError { line: 3, error: ParseError { field: Field::Port, offset: 43 } };
+        assert_eq!(error.to_string(), "Line 3: Invalid port at offset 43");
+    }
+
+    #[test]
+    fn test_parse_sdp() {
+        let sdp = b"v=0\r\n\
+                    o=- 4611731400430051336 2 IN IP4 127.0.0.1\r\n\
+                    a=group:BUNDLE 0 1\r\n\
+                    m=audio 9 UDP/TLS/RTP/SAVPF 111\r\n\
+                    a=candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host\r\n\
+                    a=mid:0\r\n\
+                    a=candidate:2 1 udp 1686052607 1.2.3.4 x typ srflx\r\n\
+                    m=video 9 UDP/TLS/RTP/SAVPF 96\r\n\
+                    a=candidate:3 1 udp 41885439 5.148.189.205 63293 typ relay\r\n\
+                    a=end-of-candidates\r\n";
+        let candidates = parse_sdp(sdp);
+        assert_eq!(candidates.len(), 3);
+        assert_eq!(candidates[0].line, 5);
+        assert_eq!(candidates[0].m_line_index, Some(0));
+        assert_eq!(candidates[0].mid, Some("0".into()));
+        assert_eq!(candidates[0].result.as_ref().unwrap().foundation, "1");
+        assert_eq!(candidates[1].line, 7);
+        assert_eq!(candidates[1].m_line_index, Some(0));
+        assert_eq!(candidates[1].mid, Some("0".into()));
+        assert_eq!(candidates[1].result, Err(ParseError { field: Field::Port, offset: 39 }));
+        assert_eq!(candidates[2].line, 9);
+        assert_eq!(candidates[2].m_line_index, Some(1));
+        assert_eq!(candidates[2].mid, None);
+        assert_eq!(candidates[2].result.as_ref().unwrap().foundation, "3");
+    }
+
+    #[test]
+    fn test_parse_sdp_candidate_list() {
+        let candidates = parse_sdp(b"candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host\n\ncandidate:2");
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(candidates[0].m_line_index, None);
+        assert_eq!(candidates[0].mid, None);
+        assert!(candidates[0].result.is_ok());
+        assert_eq!(candidates[1].line, 3);
+        assert!(candidates[1].result.is_err());
+    }
+}
@@ -0,0 +1,301 @@
+//! Diffing of two collections of ICE candidates.
+use std::collections::{HashMap, BTreeSet};
+use std::fmt;
+use std::net::IpAddr;
+
+use ::types::{IceCandidate, CandidateType, Transport};
+
+/// A change of a single field between two versions of a candidate.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum FieldChange {
+    Transport(Transport, Transport),
+    Priority(u64, u64),
+    ConnectionAddress(IpAddr, IpAddr),
+    Port(u16, u16),
+    CandidateType(CandidateType, CandidateType),
+    RelAddr(Option<IpAddr>, Option<IpAddr>),
+    RelPort(Option<u16>, Option<u16>),
+    /// An extension was added, removed or changed. A value of `None` means
+    /// that the extension is not present in that version of the candidate.
+    Extension {
+        name: Vec<u8>,
+        old: Option<Vec<u8>>,
+        new: Option<Vec<u8>>,
+    },
+}
+
+/// A candidate that is present in both collections, but with different
+/// values.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CandidateChange {
+    pub old: IceCandidate,
+    pub new: IceCandidate,
+    pub changes: Vec<FieldChange>,
+}
+
+/// The difference between two collections of candidates.
+///
+/// Candidates are matched by their foundation and component ID.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct CandidateDiff {
+    /// Candidates that are only present in the new collection.
+    pub added: Vec<IceCandidate>,
+    /// Candidates that are only present in the old collection.
+    pub removed: Vec<IceCandidate>,
+    /// Candidates that are present in both collections with different values.
+    pub changed: Vec<CandidateChange>,
+}
+
+impl CandidateDiff {
+    /// Return whether the two collections contain the same candidates.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compare two collections of candidates.
+///
+/// Candidates are matched by their foundation and component ID. If a
+/// collection contains multiple candidates with the same foundation and
+/// component ID, the last one is used. The order of the input is preserved:
+/// Removed candidates are listed in the order of the old collection, added
+/// and changed candidates in the order of the new collection.
+pub fn diff<'a, I, J>(old: I, new: J) -> CandidateDiff
+    where I: IntoIterator<Item=&'a IceCandidate>,
+          J: IntoIterator<Item=&'a IceCandidate>
+{
+    let old = keyed(old);
+    let new = keyed(new);
+    let old_index: HashMap<(&str, u32), &IceCandidate> = old.iter()
+        .map(|c| ((c.foundation.as_str(), c.component_id), *c))
+        .collect();
+    let new_index: HashMap<(&str, u32), &IceCandidate> = new.iter()
+        .map(|c| ((c.foundation.as_str(), c.component_id), *c))
+        .collect();
+
+    let mut result = CandidateDiff::default();
+    for candidate in &old {
+        if !new_index.contains_key(&(candidate.foundation.as_str(), candidate.component_id)) {
+            result.removed.push((*candidate).clone());
+        }
+    }
+    for candidate in &new {
+        match old_index.get(&(candidate.foundation.as_str(), candidate.component_id)) {
+            None => result.added.push((*candidate).clone()),
+            Some(previous) => {
+                let changes = field_changes(previous, candidate);
+                if !changes.is_empty() {
+                    result.changed.push(CandidateChange {
+                        old: (*previous).clone(),
+                        new: (*candidate).clone(),
+                        changes,
+                    });
+                }
+            },
+        }
+    }
+    result
+}
+
+/// Deduplicate the candidates by foundation and component ID. The last
+/// candidate wins, but the position of the first one is kept.
+fn keyed<'a, I>(candidates: I) -> Vec<&'a IceCandidate>
+    where I: IntoIterator<Item=&'a IceCandidate>
+{
+    let mut positions: HashMap<(&str, u32), usize> = HashMap::new();
+    let mut result: Vec<&IceCandidate> = vec![];
+    for candidate in candidates {
+        let key = (candidate.foundation.as_str(), candidate.component_id);
+        match positions.get(&key) {
+            Some(&pos) => result[pos] = candidate,
+            None => {
+                positions.insert(key, result.len());
+                result.push(candidate);
+            },
+        }
+    }
+    result
+}
+
+/// Return all field level changes between two candidates.
+fn field_changes(old: &IceCandidate, new: &IceCandidate) -> Vec<FieldChange> {
+    let mut changes = vec![];
+    if old.transport != new.transport {
+        changes.push(FieldChange::Transport(old.transport.clone(), new.transport.clone()));
+    }
+    if old.priority != new.priority {
+        changes.push(FieldChange::Priority(old.priority, new.priority));
+    }
+    if old.connection_address != new.connection_address {
+        changes.push(FieldChange::ConnectionAddress(old.connection_address, new.connection_address));
+    }
+    if old.port != new.port {
+        changes.push(FieldChange::Port(old.port, new.port));
+    }
+    if old.candidate_type != new.candidate_type {
+        changes.push(FieldChange::CandidateType(old.candidate_type.clone(), new.candidate_type.clone()));
+    }
+    if old.rel_addr != new.rel_addr {
+        changes.push(FieldChange::RelAddr(old.rel_addr, new.rel_addr));
+    }
+    if old.rel_port != new.rel_port {
+        changes.push(FieldChange::RelPort(old.rel_port, new.rel_port));
+    }
+
+    // Compare extensions in a deterministic order
+    let empty = HashMap::new();
+    let old_ext = old.extensions.as_ref().unwrap_or(&empty);
+    let new_ext = new.extensions.as_ref().unwrap_or(&empty);
+    let names: BTreeSet<&Vec<u8>> = old_ext.keys().chain(new_ext.keys()).collect();
+    for name in names {
+        let old_val = old_ext.get(name);
+        let new_val = new_ext.get(name);
+        if old_val != new_val {
+            changes.push(FieldChange::Extension {
+                name: name.clone(),
+                old: old_val.cloned(),
+                new: new_val.cloned(),
+            });
+        }
+    }
+
+    changes
+}
+
+/// Helper to display optional values, using `-` for `None`.
+struct Opt<'a, T: 'a>(&'a Option<T>);
+
+impl<'a, T: fmt::Display> fmt::Display for Opt<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self.0 {
+            Some(ref val) => write!(f, "{}", val),
+            None => write!(f, "-"),
+        }
+    }
+}
+
+/// Helper to display optional byte strings, using `-` for `None`.
+struct OptBytes<'a>(&'a Option<Vec<u8>>);
+
+impl<'a> fmt::Display for OptBytes<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self.0 {
+            Some(ref val) => write!(f, "{}", String::from_utf8_lossy(val)),
+            None => write!(f, "-"),
+        }
+    }
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FieldChange::Transport(ref a, ref b) => write!(f, "transport: {} -> {}", a, b),
+            FieldChange::Priority(a, b) => write!(f, "priority: {} -> {}", a, b),
+            FieldChange::ConnectionAddress(a, b) => write!(f, "connection-address: {} -> {}", a, b),
+            FieldChange::Port(a, b) => write!(f, "port: {} -> {}", a, b),
+            FieldChange::CandidateType(ref a, ref b) => write!(f, "typ: {} -> {}", a, b),
+            FieldChange::RelAddr(ref a, ref b) => write!(f, "raddr: {} -> {}", Opt(a), Opt(b)),
+            FieldChange::RelPort(ref a, ref b) => write!(f, "rport: {} -> {}", Opt(a), Opt(b)),
+            FieldChange::Extension { ref name, ref old, ref new } => {
+                write!(f, "{}: {} -> {}", String::from_utf8_lossy(name), OptBytes(old), OptBytes(new))
+            },
+        }
+    }
+}
+
+impl fmt::Display for CandidateDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for candidate in &self.removed {
+            writeln!(f, "- {} {} {}:{} typ {}", candidate.foundation, candidate.component_id,
+                     candidate.connection_address, candidate.port, candidate.candidate_type)?;
+        }
+        for candidate in &self.added {
+            writeln!(f, "+ {} {} {}:{} typ {}", candidate.foundation, candidate.component_id,
+                     candidate.connection_address, candidate.port, candidate.candidate_type)?;
+        }
+        for change in &self.changed {
+            writeln!(f, "~ {} {}", change.new.foundation, change.new.component_id)?;
+            for field in &change.changes {
+                writeln!(f, "    {}", field)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use ::parse;
+
+    use super::*;
+
+    fn candidate(sdp: &str) -> IceCandidate {
+        parse(sdp.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_diff_equal() {
+        let candidates = vec![
+            candidate("candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host generation 0"),
+            candidate("candidate:2 1 udp 1686052607 1.2.3.4 46154 typ srflx raddr 10.0.0.17 rport 46154"),
+        ];
+        let result = diff(&candidates, &candidates);
+        assert!(result.is_empty());
+        assert_eq!(result.to_string(), "");
+    }
+
+    #[test]
+    fn test_diff_added_removed() {
+        let old = vec![
+            candidate("candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host"),
+            candidate("candidate:2 1 udp 1686052607 1.2.3.4 46154 typ srflx raddr 10.0.0.17 rport 46154"),
+        ];
+        let new = vec![
+            candidate("candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host"),
+            candidate("candidate:1 2 udp 2130706430 10.0.0.17 46155 typ host"),
+        ];
+        let result = diff(&old, &new);
+        assert_eq!(result.removed, vec![old[1].clone()]);
+        assert_eq!(result.added, vec![new[1].clone()]);
+        assert!(result.changed.is_empty());
+        assert_eq!(result.to_string(), "- 2 1 1.2.3.4:46154 typ srflx\n+ 1 2 10.0.0.17:46155 typ host\n");
+    }
+
+    #[test]
+    fn test_diff_changed() {
+        let old = vec![
+            candidate("candidate:2 1 udp 1686052607 1.2.3.4 46154 typ srflx raddr 10.0.0.17 rport 46154 generation 0 ufrag EEtu"),
+        ];
+        let new = vec![
+            candidate("candidate:2 1 udp 1686052606 1.2.3.5 46154 typ srflx raddr 10.0.0.17 generation 1 network-id 3"),
+        ];
+        let result = diff(&old, &new);
+        assert!(result.added.is_empty());
+        assert!(result.removed.is_empty());
+        assert_eq!(result.changed.len(), 1);
+        assert_eq!(result.changed[0].changes, vec![
+            FieldChange::Priority(1686052607, 1686052606),
+            FieldChange::ConnectionAddress("1.2.3.4".parse().unwrap(), "1.2.3.5".parse().unwrap()),
+            FieldChange::RelPort(Some(46154), None),
+            FieldChange::Extension { name: b"generation".to_vec(), old: Some(b"0".to_vec()), new: Some(b"1".to_vec()) },
+            FieldChange::Extension { name: b"network-id".to_vec(), old: None, new: Some(b"3".to_vec()) },
+            FieldChange::Extension { name: b"ufrag".to_vec(), old: Some(b"EEtu".to_vec()), new: None },
+        ]);
+        assert_eq!(result.to_string(), "~ 2 1\n    priority: 1686052607 -> 1686052606\n    \
+                                        connection-address: 1.2.3.4 -> 1.2.3.5\n    rport: 46154 -> -\n    \
+                                        generation: 0 -> 1\n    network-id: - -> 3\n    ufrag: EEtu -> -\n");
+    }
+
+    #[test]
+    fn test_diff_duplicate_keys() {
+        let old = vec![
+            candidate("candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host"),
+            candidate("candidate:1 1 udp 2130706431 10.0.0.18 46154 typ host"),
+        ];
+        let new = vec![
+            candidate("candidate:1 1 udp 2130706431 10.0.0.18 46154 typ host"),
+        ];
+        assert!(diff(&old, &new).is_empty());
+    }
+}
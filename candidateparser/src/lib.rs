@@ -2,18 +2,55 @@
 //!
 //! The main entry point for this library is the [`parse`](fn.parse.html)
 //! function. See the docs of that function for more information.
+//!
+//! ## Features
+//!
+//! - `parsers` (enabled by default): Provide the nom based parsers in the
+//!   [`parsers`](parsers/index.html) module. The [`parse`](fn.parse.html)
+//!   function does not depend on them, so you can disable this feature to
+//!   get rid of the nom dependency. Requires `std`.
+//! - `std` (enabled by default): Use the standard library. Without this
+//!   feature, the crate is `no_std` and only requires `alloc`. The
+//!   [`parse`](fn.parse.html), [`try_parse`](fn.try_parse.html) and
+//!   [`IceCandidate::to_sdp`](struct.IceCandidate.html#method.to_sdp)
+//!   functions are available in both modes. The extensions are stored in a
+//!   `BTreeMap` instead of a `HashMap` and the IP address types are taken
+//!   from `core::net`, which are identical to the ones in `std::net`.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![cfg_attr(feature="clippy", feature(plugin))]
 #![cfg_attr(feature="clippy", plugin(clippy))]
 
+#[macro_use]
+extern crate alloc;
+#[cfg(any(feature = "std", test))]
+extern crate core;
+#[cfg(feature = "parsers")]
 #[macro_use]
 extern crate nom;
 
+mod batch;
+#[cfg(feature = "std")]
+pub mod diff;
+#[cfg(feature = "std")]
+pub mod nat;
+#[cfg(feature = "parsers")]
 pub mod parsers;
+mod reader;
+#[cfg(feature = "std")]
+mod session;
+#[cfg(feature = "std")]
+mod set;
+mod stream;
 mod types;
 
-use nom::IResult::{Done, Error, Incomplete};
-
-pub use types::{IceCandidate, CandidateType, Transport};
+pub use batch::{Batch, LineError, LineResult, SdpCandidate, parse_lines, parse_buffer, parse_sdp};
+pub use reader::{ParseError, Field, parse_foundation, parse_transport, parse_candidate_type, parse_address};
+#[cfg(feature = "std")]
+pub use session::{SessionTracker, SessionId, TrackOutcome};
+#[cfg(feature = "std")]
+pub use set::{IceCandidateSet, CandidateKey, InsertOutcome, Iter};
+pub use stream::StreamParser;
+pub use types::{IceCandidate, CandidateType, Transport, ExtensionMap};
 
 /// Parse an SDP bytestring, return an
 /// [`IceCandidate`](struct.IceCandidate.html) struct.
@@ -43,28 +80,29 @@ pub use types::{IceCandidate, CandidateType, Transport};
 /// }
 /// ```
 ///
-/// If parsing fails, `None` is returned.
+/// If parsing fails, `None` is returned. Use [`try_parse`](fn.try_parse.html)
+/// to find out why parsing failed.
 pub fn parse(sdp: &[u8]) -> Option<types::IceCandidate> {
-    match parsers::ice_candidate(sdp) {
-        Done(i, o) => {
-            if i.is_empty() {
-                Some(o)
-            } else {
-                None
-            }
-        },
-        Incomplete(_) | Error(_) => None,
-    }
+    try_parse(sdp).ok()
+}
+
+/// Parse an SDP bytestring, return an
+/// [`IceCandidate`](struct.IceCandidate.html) struct.
+///
+/// If parsing fails, a [`ParseError`](struct.ParseError.html) containing the
+/// failing field and its offset is returned.
+pub fn try_parse(sdp: &[u8]) -> Result<types::IceCandidate, ParseError> {
+    reader::ice_candidate(sdp)
 }
 
 #[cfg(test)]
 mod tests {
 
-    use std::net::{IpAddr, Ipv4Addr};
+    use core::net::{IpAddr, Ipv4Addr};
 
     use ::types::{Transport, CandidateType, IceCandidate};
 
-    use super::parse;
+    use super::{parse, try_parse, ParseError, Field};
 
     #[test]
     fn test_parse_full() {
@@ -109,4 +147,11 @@ mod tests {
         assert_eq!(parsed, None);
     }
 
+    #[test]
+    fn test_try_parse_invalid() {
+        let candidate = b"candidate:373990095 1 udp 41885439 asdf 5.148.189.205 63293 typ relay";
+        let error = try_parse(&candidate[..]).unwrap_err();
+        assert_eq!(error, ParseError { field: Field::ConnectionAddress, offset: 35 });
+    }
+
 }
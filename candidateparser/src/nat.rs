@@ -0,0 +1,287 @@
+//! Inference of the NAT behavior of a peer from its candidates.
+//!
+//! The results are heuristics: A peer only reveals what its candidates
+//! contain. Browsers for example often hide the related address of server
+//! reflexive candidates (by using `0.0.0.0`), in which case the mapping
+//! behavior cannot be determined.
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use ::types::{IceCandidate, CandidateType, Transport};
+
+/// Whether the peer is located behind a NAT.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum NatPresence {
+    /// The peer has a public address and no address translation takes place.
+    Absent,
+    /// At least one reflexive candidate differs from its base.
+    Present,
+    /// The candidates do not contain enough information.
+    Unknown,
+}
+
+/// The NAT mapping behavior (RFC4787 section 4.1).
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum MappingBehavior {
+    /// The same base is always mapped to the same reflexive transport
+    /// address, independent of the destination.
+    EndpointIndependent,
+    /// The same base is mapped to different reflexive transport addresses
+    /// (a "symmetric NAT").
+    EndpointDependent,
+    /// The candidates do not contain enough information. At least two
+    /// reflexive candidates with the same known base are required.
+    Unknown,
+}
+
+/// The number of candidates per candidate type.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+pub struct CandidateCounts {
+    pub host: usize,
+    pub srflx: usize,
+    pub prflx: usize,
+    pub relay: usize,
+    pub other: usize,
+}
+
+/// A structured report about the connectivity of a peer.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ConnectivityReport {
+    /// The number of candidates per type.
+    pub counts: CandidateCounts,
+    /// Whether the peer is behind a NAT.
+    pub nat: NatPresence,
+    /// The mapping behavior of the NAT.
+    pub mapping: MappingBehavior,
+    /// Whether the NAT preserves the port of the base. `None` if no reflexive
+    /// candidate with a known base is available.
+    pub port_preservation: Option<bool>,
+    /// Whether any candidate uses the shared address space `100.64.0.0/10`
+    /// (RFC6598), which indicates a carrier grade NAT.
+    pub cgnat: bool,
+    /// Whether the peer has IPv4 candidates.
+    pub ipv4: bool,
+    /// Whether the peer has IPv6 candidates.
+    pub ipv6: bool,
+    /// Whether the peer offers relayed candidates.
+    pub relay_available: bool,
+    /// The public addresses of the peer (from reflexive candidates and from
+    /// host candidates with a public address), sorted and deduplicated.
+    pub public_addresses: Vec<IpAddr>,
+}
+
+/// Analyze the candidates of a peer.
+pub fn analyze(candidates: &[IceCandidate]) -> ConnectivityReport {
+    let mut counts = CandidateCounts::default();
+    let mut cgnat = false;
+    let mut ipv4 = false;
+    let mut ipv6 = false;
+    let mut public_addresses = vec![];
+    let mut translated = false;
+    let mut untranslated = false;
+    let mut public_host = false;
+    let mut preserved = None;
+    let mut mappings: HashMap<(u32, &Transport, SocketAddr), (Vec<SocketAddr>, usize)> = HashMap::new();
+
+    for candidate in candidates {
+        let address = candidate.connection_address;
+        match address {
+            IpAddr::V4(_) => ipv4 = true,
+            IpAddr::V6(_) => ipv6 = true,
+        }
+        if is_shared(&address) || candidate.rel_addr.as_ref().is_some_and(is_shared) {
+            cgnat = true;
+        }
+
+        let reflexive = match candidate.candidate_type {
+            CandidateType::Host => {
+                counts.host += 1;
+                if is_public(&address) {
+                    public_host = true;
+                    public_addresses.push(address);
+                }
+                false
+            },
+            CandidateType::Srflx => { counts.srflx += 1; true },
+            CandidateType::Prflx => { counts.prflx += 1; true },
+            CandidateType::Relay => { counts.relay += 1; false },
+            CandidateType::Token(_) => { counts.other += 1; false },
+        };
+        if !reflexive {
+            continue;
+        }
+
+        if is_public(&address) {
+            public_addresses.push(address);
+        }
+        let base = match candidate.base() {
+            Some(base) if !base.ip().is_unspecified() => base,
+            _ => continue,
+        };
+        let mapped = SocketAddr::new(address, candidate.port);
+        if mapped == base {
+            untranslated = true;
+        } else {
+            translated = true;
+        }
+        let port_preserved = mapped.port() == base.port();
+        preserved = Some(preserved.unwrap_or(true) && port_preserved);
+        let entry = mappings.entry((candidate.component_id, &candidate.transport, base)).or_default();
+        if !entry.0.contains(&mapped) {
+            entry.0.push(mapped);
+        }
+        entry.1 += 1;
+    }
+
+    let nat = if translated {
+        NatPresence::Present
+    } else if untranslated || (public_host && counts.srflx + counts.prflx == 0) {
+        NatPresence::Absent
+    } else {
+        NatPresence::Unknown
+    };
+
+    let mapping = if mappings.values().any(|(mapped, _)| mapped.len() > 1) {
+        MappingBehavior::EndpointDependent
+    } else if mappings.values().any(|&(_, observations)| observations > 1) {
+        MappingBehavior::EndpointIndependent
+    } else {
+        MappingBehavior::Unknown
+    };
+
+    public_addresses.sort();
+    public_addresses.dedup();
+
+    ConnectivityReport {
+        counts,
+        nat,
+        mapping,
+        port_preservation: preserved,
+        cgnat,
+        ipv4,
+        ipv6,
+        relay_available: counts.relay > 0,
+        public_addresses,
+    }
+}
+
+/// Return whether the address is part of the shared address space
+/// `100.64.0.0/10` (RFC6598).
+fn is_shared(addr: &IpAddr) -> bool {
+    match *addr {
+        IpAddr::V4(ip) => ip.octets()[0] == 100 && (ip.octets()[1] & 0xc0) == 64,
+        IpAddr::V6(_) => false,
+    }
+}
+
+/// Return whether the address is publicly routable.
+fn is_public(addr: &IpAddr) -> bool {
+    match *addr {
+        IpAddr::V4(ip) => is_public_v4(&ip),
+        IpAddr::V6(ip) => is_public_v6(&ip),
+    }
+}
+
+fn is_public_v4(ip: &Ipv4Addr) -> bool {
+    !(ip.is_private() || ip.is_loopback() || ip.is_link_local() || ip.is_unspecified()
+      || ip.is_broadcast() || ip.is_multicast() || is_shared(&IpAddr::V4(*ip)))
+}
+
+fn is_public_v6(ip: &Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    let unique_local = (first & 0xfe00) == 0xfc00;
+    let link_local = (first & 0xffc0) == 0xfe80;
+    !(ip.is_loopback() || ip.is_unspecified() || ip.is_multicast() || unique_local || link_local)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use ::parse;
+
+    use super::*;
+
+    fn candidates(sdps: &[&str]) -> Vec<IceCandidate> {
+        sdps.iter().map(|sdp| parse(sdp.as_bytes()).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_analyze_empty() {
+        let report = analyze(&[]);
+        assert_eq!(report.counts, CandidateCounts::default());
+        assert_eq!(report.nat, NatPresence::Unknown);
+        assert_eq!(report.mapping, MappingBehavior::Unknown);
+        assert_eq!(report.port_preservation, None);
+        assert!(!report.cgnat);
+        assert!(!report.relay_available);
+    }
+
+    #[test]
+    fn test_analyze_nat() {
+        let report = analyze(&candidates(&[
+            "candidate:1 1 udp 2130706431 192.168.1.10 46154 typ host",
+            "candidate:2 1 udp 1686052607 1.2.3.4 46154 typ srflx raddr 192.168.1.10 rport 46154",
+            "candidate:3 1 udp 41885439 5.148.189.205 63293 typ relay raddr 1.2.3.4 rport 46154",
+        ]));
+        assert_eq!(report.counts, CandidateCounts { host: 1, srflx: 1, prflx: 0, relay: 1, other: 0 });
+        assert_eq!(report.nat, NatPresence::Present);
+        assert_eq!(report.mapping, MappingBehavior::Unknown);
+        assert_eq!(report.port_preservation, Some(true));
+        assert!(report.ipv4);
+        assert!(!report.ipv6);
+        assert!(report.relay_available);
+        assert_eq!(report.public_addresses, vec!["1.2.3.4".parse::<IpAddr>().unwrap()]);
+    }
+
+    #[test]
+    fn test_analyze_no_nat() {
+        let report = analyze(&candidates(&[
+            "candidate:1 1 udp 2130706431 1.2.3.4 46154 typ host",
+            "candidate:2 1 udp 1686052607 1.2.3.4 46154 typ srflx raddr 1.2.3.4 rport 46154",
+        ]));
+        assert_eq!(report.nat, NatPresence::Absent);
+
+        let report = analyze(&candidates(&[
+            "candidate:1 1 udp 2130706431 2001:db8::1 46154 typ host",
+        ]));
+        assert_eq!(report.nat, NatPresence::Absent);
+        assert!(report.ipv6);
+    }
+
+    #[test]
+    fn test_analyze_symmetric() {
+        let report = analyze(&candidates(&[
+            "candidate:1 1 udp 2130706431 192.168.1.10 46154 typ host",
+            "candidate:2 1 udp 1686052607 1.2.3.4 50000 typ srflx raddr 192.168.1.10 rport 46154",
+            "candidate:3 1 udp 1686052607 1.2.3.4 50001 typ srflx raddr 192.168.1.10 rport 46154",
+        ]));
+        assert_eq!(report.nat, NatPresence::Present);
+        assert_eq!(report.mapping, MappingBehavior::EndpointDependent);
+        assert_eq!(report.port_preservation, Some(false));
+
+        let report = analyze(&candidates(&[
+            "candidate:2 1 udp 1686052607 1.2.3.4 50000 typ srflx raddr 192.168.1.10 rport 46154",
+            "candidate:3 1 udp 1845501695 1.2.3.4 50000 typ prflx raddr 192.168.1.10 rport 46154",
+        ]));
+        assert_eq!(report.mapping, MappingBehavior::EndpointIndependent);
+    }
+
+    #[test]
+    fn test_analyze_hidden_base() {
+        let report = analyze(&candidates(&[
+            "candidate:2 1 udp 1686052607 1.2.3.4 50000 typ srflx raddr 0.0.0.0 rport 0",
+        ]));
+        assert_eq!(report.nat, NatPresence::Unknown);
+        assert_eq!(report.port_preservation, None);
+    }
+
+    #[test]
+    fn test_analyze_cgnat() {
+        let report = analyze(&candidates(&[
+            "candidate:1 1 udp 2130706431 100.72.1.10 46154 typ host",
+            "candidate:2 1 udp 1686052607 1.2.3.4 50000 typ srflx raddr 100.72.1.10 rport 46154",
+        ]));
+        assert!(report.cgnat);
+        assert_eq!(report.public_addresses, vec!["1.2.3.4".parse::<IpAddr>().unwrap()]);
+    }
+}
@@ -0,0 +1,471 @@
+//! Hand-written byte level parser for the ICE candidates.
+//!
+//! This parser implements the same grammar as the nom parsers in the
+//! [`parsers`](../parsers/index.html) module, but it reports the field and
+//! the offset at which parsing failed.
+use core::fmt;
+use core::net::IpAddr;
+use core::str;
+
+use alloc::string::{String, ToString};
+#[cfg(feature = "std")]
+use std::error;
+
+use ::types::{IceCandidate, CandidateType, Transport, ExtensionMap};
+
+/// The part of the candidate in which parsing failed.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Field {
+    /// The `candidate:` prefix.
+    Prefix,
+    Foundation,
+    ComponentId,
+    Transport,
+    Priority,
+    ConnectionAddress,
+    Port,
+    CandidateType,
+    /// The related address. When parsing an entire candidate, an invalid
+    /// related address is treated as extension instead, so this is only
+    /// reported by [`parse_address`](fn.parse_address.html).
+    RelAddr,
+    /// The related port. Like the related address, this is only reported
+    /// when validating a single value, e.g. by a binding that converts a
+    /// port of a wider integer type.
+    RelPort,
+    /// An extension, or any other data following the fixed fields.
+    Extension,
+    /// The entire line, because it exceeded the maximum line length of a
+    /// [`StreamParser`](struct.StreamParser.html).
+    LineLength,
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Field::Prefix => "candidate prefix",
+            Field::Foundation => "foundation",
+            Field::ComponentId => "component-id",
+            Field::Transport => "transport",
+            Field::Priority => "priority",
+            Field::ConnectionAddress => "connection-address",
+            Field::Port => "port",
+            Field::CandidateType => "cand-type",
+            Field::RelAddr => "rel-addr",
+            Field::RelPort => "rel-port",
+            Field::Extension => "extension",
+            Field::LineLength => "line length",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// An error that occurred while parsing a candidate.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct ParseError {
+    /// The field that could not be parsed.
+    pub field: Field,
+    /// The byte offset in the input at which the field starts.
+    pub offset: usize,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid {} at offset {}", self.field, self.offset)
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for ParseError {}
+
+/// A cursor over the input bytes.
+struct Reader<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(input: &'a [u8]) -> Self {
+        Reader { input, pos: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.input.len()
+    }
+
+    /// Consume the longest prefix of bytes matching the predicate. The
+    /// returned slice may be empty.
+    fn take_while<F: Fn(u8) -> bool>(&mut self, pred: F) -> &'a [u8] {
+        let start = self.pos;
+        while self.pos < self.input.len() && pred(self.input[self.pos]) {
+            self.pos += 1;
+        }
+        &self.input[start..self.pos]
+    }
+
+    /// Consume the tag if the input starts with it.
+    fn tag(&mut self, tag: &[u8]) -> bool {
+        if self.input[self.pos..].starts_with(tag) {
+            self.pos += tag.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Consume one or more spaces or tabs.
+    fn space(&mut self) -> bool {
+        !self.take_while(|c| c == b' ' || c == b'\t').is_empty()
+    }
+
+    /// Run the parser. If it fails, reset the position.
+    fn attempt<T, F: FnOnce(&mut Self) -> Option<T>>(&mut self, parser: F) -> Option<T> {
+        let pos = self.pos;
+        let result = parser(self);
+        if result.is_none() {
+            self.pos = pos;
+        }
+        result
+    }
+}
+
+/// The maximum length of a foundation.
+const MAX_FOUNDATION_LEN: usize = 32;
+
+/// The maximum number of digits of a component ID.
+const MAX_COMPONENT_ID_DIGITS: usize = 5;
+
+/// The maximum number of digits of a priority.
+const MAX_PRIORITY_DIGITS: usize = 10;
+
+/// Return whether the specified byte is a valid ice-char.
+fn is_ice_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'+' || c == b'/'
+}
+
+/// Return whether the specified byte is a valid IP char.
+fn is_ip_char(c: u8) -> bool {
+    c.is_ascii_hexdigit() || c == b'.' || c == b':'
+}
+
+/// Return whether the specified byte terminates a byte-string.
+fn is_byte_string_end(c: u8) -> bool {
+    c == 0x00 || c == 0x0a || c == 0x0d || c == 0x20
+}
+
+/// Parse a number with at most `max_digits` digits (0 means no limit).
+fn number<T: str::FromStr>(r: &mut Reader, max_digits: usize) -> Option<T> {
+    let digits = r.take_while(|c| c.is_ascii_digit());
+    if digits.is_empty() || (max_digits > 0 && digits.len() > max_digits) {
+        return None;
+    }
+    str::from_utf8(digits).ok().and_then(|d| d.parse().ok())
+}
+
+/// Parse a connection-address.
+fn ip_addr(r: &mut Reader) -> Option<IpAddr> {
+    let addr = r.take_while(is_ip_char);
+    if addr.is_empty() {
+        return None;
+    }
+    str::from_utf8(addr).ok().and_then(|a| a.parse().ok())
+}
+
+/// Parse one or more alphabetic characters.
+fn alpha<'a>(r: &mut Reader<'a>) -> Option<&'a str> {
+    let val = r.take_while(|c| c.is_ascii_alphabetic());
+    if val.is_empty() {
+        return None;
+    }
+    str::from_utf8(val).ok()
+}
+
+/// Parse a byte-string that is terminated by space, NUL, CR or LF.
+fn byte_string<'a>(r: &mut Reader<'a>) -> Option<&'a [u8]> {
+    let val = r.take_while(|c| !is_byte_string_end(c));
+    if val.is_empty() {
+        None
+    } else {
+        Some(val)
+    }
+}
+
+/// Parse a foundation (1 to 32 ice-chars).
+fn foundation(r: &mut Reader) -> Option<String> {
+    let val = r.take_while(is_ice_char);
+    if val.is_empty() || val.len() > MAX_FOUNDATION_LEN {
+        return None;
+    }
+    str::from_utf8(val).ok().map(|v| v.to_string())
+}
+
+/// Parse a transport. The `udp` transport is case insensitive.
+fn transport(r: &mut Reader) -> Option<Transport> {
+    alpha(r).map(|val| {
+        if val.eq_ignore_ascii_case("udp") {
+            Transport::Udp
+        } else {
+            Transport::Extension(val.to_string())
+        }
+    })
+}
+
+/// Parse a candidate type (without the `typ` prefix).
+fn candidate_type(r: &mut Reader) -> Option<CandidateType> {
+    alpha(r).map(|val| match val {
+        "host" => CandidateType::Host,
+        "srflx" => CandidateType::Srflx,
+        "prflx" => CandidateType::Prflx,
+        "relay" => CandidateType::Relay,
+        _ => CandidateType::Token(val.to_string()),
+    })
+}
+
+/// Run a parser for the specified field. On failure, return an error
+/// pointing at the start of the field.
+fn field<'a, T, F>(r: &mut Reader<'a>, field: Field, parser: F) -> Result<T, ParseError>
+    where F: FnOnce(&mut Reader<'a>) -> Option<T>
+{
+    let offset = r.pos;
+    parser(r).ok_or(ParseError { field, offset })
+}
+
+/// Parse a separator (one or more spaces) followed by the specified field.
+fn separated<'a, T, F>(r: &mut Reader<'a>, field: Field, parser: F) -> Result<T, ParseError>
+    where F: FnOnce(&mut Reader<'a>) -> Option<T>
+{
+    let offset = r.pos;
+    if !r.space() {
+        return Err(ParseError { field, offset });
+    }
+    self::field(r, field, parser)
+}
+
+/// Parse an entire ICE candidate. The entire input must be consumed.
+pub fn ice_candidate(input: &[u8]) -> Result<IceCandidate, ParseError> {
+    let mut r = Reader::new(input);
+
+    field(&mut r, Field::Prefix, |r| if r.tag(b"candidate:") { Some(()) } else { None })?;
+    let foundation = field(&mut r, Field::Foundation, foundation)?;
+    let component_id = separated(&mut r, Field::ComponentId, |r| number::<u32>(r, MAX_COMPONENT_ID_DIGITS))?;
+    let transport = separated(&mut r, Field::Transport, transport)?;
+    let priority = separated(&mut r, Field::Priority, |r| number::<u64>(r, MAX_PRIORITY_DIGITS))?;
+    let connection_address = separated(&mut r, Field::ConnectionAddress, ip_addr)?;
+    let port = separated(&mut r, Field::Port, |r| number::<u16>(r, 0))?;
+    let candidate_type = separated(&mut r, Field::CandidateType, |r| {
+        if !r.tag(b"typ") || !r.space() {
+            return None;
+        }
+        candidate_type(r)
+    })?;
+
+    // The related address and port are optional. If they cannot be parsed,
+    // the input is treated as extension instead.
+    let rel_addr = r.attempt(|r| {
+        if r.space() && r.tag(b"raddr") && r.space() { ip_addr(r) } else { None }
+    });
+    let rel_port = r.attempt(|r| {
+        if r.space() && r.tag(b"rport") && r.space() { number::<u16>(r, 0) } else { None }
+    });
+
+    let mut extensions = ExtensionMap::new();
+    while !r.is_empty() {
+        let (key, val) = field(&mut r, Field::Extension, |r| {
+            if !r.space() {
+                return None;
+            }
+            let key = byte_string(r)?;
+            if !r.space() {
+                return None;
+            }
+            let val = byte_string(r)?;
+            Some((key, val))
+        })?;
+        extensions.insert(key.to_vec(), val.to_vec());
+    }
+
+    Ok(IceCandidate {
+        foundation,
+        component_id,
+        transport,
+        priority,
+        connection_address,
+        port,
+        candidate_type,
+        rel_addr,
+        rel_port,
+        extensions: if extensions.is_empty() { None } else { Some(extensions) },
+    })
+}
+
+/// Parse the entire value of a single field. On failure, return an error
+/// with the offset `0`.
+fn field_value<'a, T, F>(input: &'a [u8], field: Field, parser: F) -> Result<T, ParseError>
+    where F: FnOnce(&mut Reader<'a>) -> Option<T>
+{
+    let mut r = Reader::new(input);
+    match parser(&mut r) {
+        Some(val) if r.is_empty() => Ok(val),
+        _ => Err(ParseError { field, offset: 0 }),
+    }
+}
+
+/// Parse a foundation value, e.g. to validate it before building an
+/// [`IceCandidate`](struct.IceCandidate.html) field by field.
+///
+/// The same grammar as when parsing an entire candidate is used. On failure,
+/// the offset of the error is `0`.
+pub fn parse_foundation(value: &[u8]) -> Result<String, ParseError> {
+    field_value(value, Field::Foundation, foundation)
+}
+
+/// Parse a transport value (e.g. `udp`).
+///
+/// The same grammar as when parsing an entire candidate is used. On failure,
+/// the offset of the error is `0`.
+pub fn parse_transport(value: &[u8]) -> Result<Transport, ParseError> {
+    field_value(value, Field::Transport, transport)
+}
+
+/// Parse a candidate type value (e.g. `host`), without the `typ` prefix.
+///
+/// The same grammar as when parsing an entire candidate is used. On failure,
+/// the offset of the error is `0`.
+pub fn parse_candidate_type(value: &[u8]) -> Result<CandidateType, ParseError> {
+    field_value(value, Field::CandidateType, candidate_type)
+}
+
+/// Parse an IP address value of the specified field (the connection address
+/// or the related address).
+///
+/// The same grammar as when parsing an entire candidate is used. On failure,
+/// the offset of the error is `0`.
+pub fn parse_address(field: Field, value: &[u8]) -> Result<IpAddr, ParseError> {
+    field_value(value, field, ip_addr)
+}
+
+/// Return whether the byte can be part of an extension name or value.
+///
+/// Tabs are excluded, since they are treated as separators in front of a
+/// name or value.
+fn is_extension_byte(c: u8) -> bool {
+    !is_byte_string_end(c) && c != b'\t'
+}
+
+/// Validate the fields of a candidate, so that serializing it results in a
+/// string that is parsed to the same candidate.
+///
+/// Any `IpAddr` is valid, so the addresses are not checked.
+pub fn validate(candidate: &IceCandidate) -> Result<(), ParseError> {
+    let fail = |field| Err(ParseError { field, offset: 0 });
+    parse_foundation(candidate.foundation.as_bytes())?;
+    if candidate.component_id.to_string().len() > MAX_COMPONENT_ID_DIGITS {
+        return fail(Field::ComponentId);
+    }
+    if let Transport::Extension(ref val) = candidate.transport {
+        parse_transport(val.as_bytes())?;
+    }
+    if candidate.priority.to_string().len() > MAX_PRIORITY_DIGITS {
+        return fail(Field::Priority);
+    }
+    if let CandidateType::Token(ref val) = candidate.candidate_type {
+        parse_candidate_type(val.as_bytes())?;
+    }
+    if let Some(ref extensions) = candidate.extensions {
+        for (key, val) in extensions {
+            if key.is_empty() || val.is_empty() || !key.iter().chain(val).all(|&c| is_extension_byte(c)) {
+                return fail(Field::Extension);
+            }
+        }
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use core::net::{IpAddr, Ipv4Addr};
+
+    use super::*;
+
+    fn err(field: Field, offset: usize) -> Result<IceCandidate, ParseError> {
+        Err(ParseError { field, offset })
+    }
+
+    #[test]
+    fn test_parse_minimal() {
+        let parsed = ice_candidate(b"candidate:373990095 1 udp 41885439 5.148.189.205 63293 typ relay").unwrap();
+        assert_eq!(parsed.foundation, "373990095".to_string());
+        assert_eq!(parsed.component_id, 1);
+        assert_eq!(parsed.transport, Transport::Udp);
+        assert_eq!(parsed.priority, 41885439);
+        assert_eq!(parsed.connection_address, IpAddr::V4(Ipv4Addr::new(5, 148, 189, 205)));
+        assert_eq!(parsed.port, 63293);
+        assert_eq!(parsed.candidate_type, CandidateType::Relay);
+        assert_eq!(parsed.rel_addr, None);
+        assert_eq!(parsed.rel_port, None);
+        assert!(parsed.extensions.is_none());
+    }
+
+    #[test]
+    fn test_parse_rel_fallback() {
+        // An invalid related port is treated as extension
+        let parsed = ice_candidate(b"candidate:1 1 udp 1 1.2.3.4 5 typ srflx raddr 1.2.3.4 rport 99999").unwrap();
+        assert_eq!(parsed.rel_addr, Some(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4))));
+        assert_eq!(parsed.rel_port, None);
+        assert_eq!(parsed.extension(b"rport"), Some(&b"99999"[..]));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(ice_candidate(b""), err(Field::Prefix, 0));
+        assert_eq!(ice_candidate(b"candidat:1"), err(Field::Prefix, 0));
+        assert_eq!(ice_candidate(b"candidate:-"), err(Field::Foundation, 10));
+        assert_eq!(ice_candidate(b"candidate:012345678901234567890123456789012 1"), err(Field::Foundation, 10));
+        assert_eq!(ice_candidate(b"candidate:1 123456 udp"), err(Field::ComponentId, 12));
+        assert_eq!(ice_candidate(b"candidate:1 1udp"), err(Field::Transport, 13));
+        assert_eq!(ice_candidate(b"candidate:1 1 udp 12345678901 1.2.3.4"), err(Field::Priority, 18));
+        assert_eq!(ice_candidate(b"candidate:1 1 udp 1 1.2.3.256 5"), err(Field::ConnectionAddress, 20));
+        assert_eq!(ice_candidate(b"candidate:1 1 udp 1 1.2.3.4 65536 typ host"), err(Field::Port, 28));
+        assert_eq!(ice_candidate(b"candidate:1 1 udp 1 1.2.3.4 5 typhost"), err(Field::CandidateType, 30));
+        assert_eq!(ice_candidate(b"candidate:1 1 udp 1 1.2.3.4 5 typ host foo"), err(Field::Extension, 38));
+        assert_eq!(ice_candidate(b"candidate:1 1 udp 1 1.2.3.4 5 typ host  "), err(Field::Extension, 38));
+    }
+
+    #[test]
+    fn test_parse_field_values() {
+        assert_eq!(parse_foundation(b"a+/0"), Ok("a+/0".to_string()));
+        assert_eq!(parse_foundation(b"1 2"), Err(ParseError { field: Field::Foundation, offset: 0 }));
+        assert_eq!(parse_transport(b"UDP"), Ok(Transport::Udp));
+        assert_eq!(parse_transport(b"tcp"), Ok(Transport::Extension("tcp".to_string())));
+        assert_eq!(parse_transport(b""), Err(ParseError { field: Field::Transport, offset: 0 }));
+        assert_eq!(parse_candidate_type(b"srflx"), Ok(CandidateType::Srflx));
+        assert_eq!(parse_candidate_type(b"host generation 5"), Err(ParseError { field: Field::CandidateType, offset: 0 }));
+        assert_eq!(parse_address(Field::ConnectionAddress, b"::1"), Ok("::1".parse().unwrap()));
+        assert_eq!(parse_address(Field::RelAddr, b"1.2.3.4 x"), Err(ParseError { field: Field::RelAddr, offset: 0 }));
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut candidate = ice_candidate(b"candidate:1 1 udp 1 1.2.3.4 5 typ host ufrag EEtu").unwrap();
+        assert_eq!(validate(&candidate), Ok(()));
+
+        candidate.component_id = 123456;
+        assert_eq!(validate(&candidate), Err(ParseError { field: Field::ComponentId, offset: 0 }));
+        candidate.component_id = 1;
+        candidate.priority = 12345678901;
+        assert_eq!(validate(&candidate), Err(ParseError { field: Field::Priority, offset: 0 }));
+        candidate.priority = 1;
+        candidate.candidate_type = CandidateType::Token("host raddr 1.2.3.4".to_string());
+        assert_eq!(validate(&candidate), Err(ParseError { field: Field::CandidateType, offset: 0 }));
+        candidate.candidate_type = CandidateType::Host;
+        candidate.extensions.as_mut().unwrap().insert(b"a".to_vec(), b"b c".to_vec());
+        assert_eq!(validate(&candidate), Err(ParseError { field: Field::Extension, offset: 0 }));
+    }
+
+    #[test]
+    fn test_error_display() {
+        let error = ParseError { field: Field::ConnectionAddress, offset: 20 };
+        assert_eq!(error.to_string(), "Invalid connection-address at offset 20");
+    }
+}
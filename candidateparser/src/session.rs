@@ -0,0 +1,277 @@
+//! Tracking of ICE sessions (ICE restarts) across candidate batches.
+use ::set::{IceCandidateSet, InsertOutcome};
+use ::types::IceCandidate;
+
+/// Identifies an ICE session by the `ufrag` and `generation` extensions of
+/// its candidates.
+///
+/// Both values are optional, since not all agents send them.
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Default)]
+pub struct SessionId {
+    pub ufrag: Option<Vec<u8>>,
+    pub generation: Option<u32>,
+}
+
+impl<'a> From<&'a IceCandidate> for SessionId {
+    fn from(candidate: &'a IceCandidate) -> Self {
+        SessionId {
+            ufrag: candidate.ufrag().map(|u| u.to_vec()),
+            generation: candidate.generation(),
+        }
+    }
+}
+
+/// The outcome of adding a candidate to a
+/// [`SessionTracker`](struct.SessionTracker.html).
+// The outcome is short lived, so boxing the large variant is not worth it
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum TrackOutcome {
+    /// The candidate belongs to the current session. The contained value is
+    /// the outcome of inserting it into the candidate set of that session.
+    Current(InsertOutcome),
+    /// The candidate belongs to a new session, an ICE restart was detected.
+    /// The candidates of the previous session have been discarded.
+    Restart {
+        previous: SessionId,
+        discarded: Vec<IceCandidate>,
+    },
+    /// The candidate belongs to a superseded session and has been discarded.
+    Stale,
+}
+
+/// Groups incoming candidates by ICE session and detects ICE restarts.
+///
+/// A candidate is considered to belong to a new session if its ufrag differs
+/// from the current ufrag, or if it has the same ufrag and a higher
+/// generation. Candidates with a ufrag that belonged to a previous session,
+/// or with the same ufrag and a lower generation, are stale and will be
+/// discarded.
+///
+/// Candidates without ufrag and generation are always assigned to the
+/// current session. If the current session does not know its ufrag or
+/// generation yet, these values are taken from the first candidate that
+/// provides them.
+#[derive(Debug, Clone, Default)]
+pub struct SessionTracker {
+    session: Option<SessionId>,
+    superseded_ufrags: Vec<Vec<u8>>,
+    candidates: IceCandidateSet,
+}
+
+/// How a session ID relates to the current session.
+enum Relation {
+    Current,
+    Newer,
+    Stale,
+}
+
+impl SessionTracker {
+    /// Create a new session tracker without a session.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Return the ID of the current session.
+    ///
+    /// If no candidate has been added yet, `None` is returned.
+    pub fn session(&self) -> Option<&SessionId> {
+        self.session.as_ref()
+    }
+
+    /// Return the candidates of the current session.
+    pub fn candidates(&self) -> &IceCandidateSet {
+        &self.candidates
+    }
+
+    /// Add a candidate.
+    ///
+    /// If the candidate starts a new session, the candidates of the previous
+    /// session are discarded and returned as part of the
+    /// [`TrackOutcome`](enum.TrackOutcome.html).
+    pub fn add(&mut self, candidate: IceCandidate) -> TrackOutcome {
+        let id = SessionId::from(&candidate);
+        match self.relation(&id) {
+            Relation::Current => {
+                self.adopt(id);
+                TrackOutcome::Current(self.candidates.insert(candidate))
+            },
+            Relation::Newer => {
+                let (previous, discarded) = self.restart(id);
+                self.candidates.insert(candidate);
+                TrackOutcome::Restart { previous, discarded }
+            },
+            Relation::Stale => TrackOutcome::Stale,
+        }
+    }
+
+    /// Start a new session explicitly, e.g. after receiving a new remote
+    /// description with a new ufrag.
+    ///
+    /// Return the ID of the previous session (if any) and its candidates.
+    pub fn restart(&mut self, id: SessionId) -> (SessionId, Vec<IceCandidate>) {
+        let previous = self.session.take().unwrap_or_default();
+        if let (Some(ufrag), Some(new_ufrag)) = (&previous.ufrag, &id.ufrag) {
+            if new_ufrag != ufrag && !self.superseded_ufrags.contains(ufrag) {
+                self.superseded_ufrags.push(ufrag.clone());
+            }
+        }
+        self.session = Some(id);
+        let discarded = ::std::mem::take(&mut self.candidates).into_vec();
+        (previous, discarded)
+    }
+
+    /// Determine how the specified session ID relates to the current session.
+    fn relation(&self, id: &SessionId) -> Relation {
+        let current = match self.session {
+            Some(ref session) => session,
+            None => return Relation::Current,
+        };
+        if let Some(ref ufrag) = id.ufrag {
+            if self.superseded_ufrags.contains(ufrag) {
+                return Relation::Stale;
+            }
+        }
+        // A different ufrag always means a new session, the generation is
+        // only compared within the same session
+        if let (Some(current_ufrag), Some(ufrag)) = (&current.ufrag, &id.ufrag) {
+            if current_ufrag != ufrag {
+                return Relation::Newer;
+            }
+        }
+        match (current.generation, id.generation) {
+            (Some(current_gen), Some(gen)) if gen < current_gen => Relation::Stale,
+            (Some(current_gen), Some(gen)) if gen > current_gen => Relation::Newer,
+            _ => Relation::Current,
+        }
+    }
+
+    /// Fill in unknown values of the current session ID.
+    fn adopt(&mut self, id: SessionId) {
+        let session = self.session.get_or_insert_with(Default::default);
+        if session.ufrag.is_none() {
+            session.ufrag = id.ufrag;
+        }
+        if session.generation.is_none() {
+            session.generation = id.generation;
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use ::parse;
+
+    use super::*;
+
+    fn candidate(sdp: &str) -> IceCandidate {
+        parse(sdp.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_first_candidate() {
+        let mut tracker = SessionTracker::new();
+        assert_eq!(tracker.session(), None);
+        let outcome = tracker.add(candidate("candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host generation 0 ufrag EEtu"));
+        assert_eq!(outcome, TrackOutcome::Current(InsertOutcome::Inserted));
+        assert_eq!(tracker.session(), Some(&SessionId { ufrag: Some(b"EEtu".to_vec()), generation: Some(0) }));
+        assert_eq!(tracker.candidates().len(), 1);
+    }
+
+    #[test]
+    fn test_same_session() {
+        let mut tracker = SessionTracker::new();
+        tracker.add(candidate("candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host generation 0 ufrag EEtu"));
+        let outcome = tracker.add(candidate("candidate:2 1 udp 1686052607 1.2.3.4 46154 typ srflx raddr 10.0.0.17 rport 46154 generation 0 ufrag EEtu"));
+        assert_eq!(outcome, TrackOutcome::Current(InsertOutcome::Inserted));
+        let outcome = tracker.add(candidate("candidate:3 1 udp 41885439 5.148.189.205 63293 typ relay"));
+        assert_eq!(outcome, TrackOutcome::Current(InsertOutcome::Inserted));
+        assert_eq!(tracker.candidates().len(), 3);
+    }
+
+    #[test]
+    fn test_adopt_missing_values() {
+        let mut tracker = SessionTracker::new();
+        tracker.add(candidate("candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host"));
+        assert_eq!(tracker.session(), Some(&SessionId { ufrag: None, generation: None }));
+        tracker.add(candidate("candidate:2 1 udp 2130706431 10.0.0.18 46154 typ host ufrag EEtu"));
+        assert_eq!(tracker.session(), Some(&SessionId { ufrag: Some(b"EEtu".to_vec()), generation: None }));
+        assert_eq!(tracker.candidates().len(), 2);
+    }
+
+    #[test]
+    fn test_restart_by_ufrag() {
+        let mut tracker = SessionTracker::new();
+        let old = candidate("candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host ufrag EEtu");
+        tracker.add(old.clone());
+        let outcome = tracker.add(candidate("candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host ufrag Ab12"));
+        assert_eq!(outcome, TrackOutcome::Restart {
+            previous: SessionId { ufrag: Some(b"EEtu".to_vec()), generation: None },
+            discarded: vec![old.clone()],
+        });
+        assert_eq!(tracker.candidates().len(), 1);
+
+        // Retransmit of a candidate from the old session
+        assert_eq!(tracker.add(old), TrackOutcome::Stale);
+        assert_eq!(tracker.candidates().len(), 1);
+    }
+
+    #[test]
+    fn test_restart_by_generation() {
+        let mut tracker = SessionTracker::new();
+        tracker.add(candidate("candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host generation 0"));
+        let outcome = tracker.add(candidate("candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host generation 1"));
+        match outcome {
+            TrackOutcome::Restart { previous, discarded } => {
+                assert_eq!(previous.generation, Some(0));
+                assert_eq!(discarded.len(), 1);
+            },
+            other => panic!("Unexpected outcome: {:?}", other),
+        }
+        assert_eq!(tracker.session().unwrap().generation, Some(1));
+
+        let stale = candidate("candidate:2 1 udp 2130706431 10.0.0.18 46154 typ host generation 0");
+        assert_eq!(tracker.add(stale), TrackOutcome::Stale);
+        assert_eq!(tracker.candidates().len(), 1);
+    }
+
+    #[test]
+    fn test_explicit_restart() {
+        let mut tracker = SessionTracker::new();
+        tracker.add(candidate("candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host ufrag EEtu"));
+        let (previous, discarded) = tracker.restart(SessionId { ufrag: Some(b"Ab12".to_vec()), generation: None });
+        assert_eq!(previous.ufrag, Some(b"EEtu".to_vec()));
+        assert_eq!(discarded.len(), 1);
+        assert!(tracker.candidates().is_empty());
+        let stale = candidate("candidate:2 1 udp 2130706431 10.0.0.18 46154 typ host ufrag EEtu");
+        assert_eq!(tracker.add(stale), TrackOutcome::Stale);
+    }
+
+    #[test]
+    fn test_restart_by_ufrag_with_lower_generation() {
+        let mut tracker = SessionTracker::new();
+        tracker.add(candidate("candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host generation 1 ufrag EEtu"));
+        let outcome = tracker.add(candidate("candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host generation 0 ufrag Ab12"));
+        match outcome {
+            TrackOutcome::Restart { previous, discarded } => {
+                assert_eq!(previous, SessionId { ufrag: Some(b"EEtu".to_vec()), generation: Some(1) });
+                assert_eq!(discarded.len(), 1);
+            },
+            other => panic!("Unexpected outcome: {:?}", other),
+        }
+        assert_eq!(tracker.session(), Some(&SessionId { ufrag: Some(b"Ab12".to_vec()), generation: Some(0) }));
+    }
+
+    #[test]
+    fn test_explicit_restart_without_ufrag() {
+        let mut tracker = SessionTracker::new();
+        tracker.add(candidate("candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host ufrag EEtu"));
+        tracker.restart(SessionId { ufrag: None, generation: Some(1) });
+
+        // The ufrag is still valid, so the candidate is not stale
+        let outcome = tracker.add(candidate("candidate:2 1 udp 2130706431 10.0.0.18 46154 typ host generation 1 ufrag EEtu"));
+        assert_eq!(outcome, TrackOutcome::Current(InsertOutcome::Inserted));
+        assert_eq!(tracker.session(), Some(&SessionId { ufrag: Some(b"EEtu".to_vec()), generation: Some(1) }));
+    }
+}
@@ -0,0 +1,351 @@
+//! A container for ICE candidates with deduplication and redundancy
+//! elimination.
+use std::collections::HashMap;
+use std::collections::hash_map;
+use std::net::IpAddr;
+use std::vec;
+
+use ::types::{IceCandidate, Transport};
+
+/// The key identifying a candidate inside an
+/// [`IceCandidateSet`](struct.IceCandidateSet.html).
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct CandidateKey {
+    pub foundation: String,
+    pub component_id: u32,
+    pub transport: Transport,
+    pub connection_address: IpAddr,
+    pub port: u16,
+}
+
+impl<'a> From<&'a IceCandidate> for CandidateKey {
+    fn from(candidate: &'a IceCandidate) -> Self {
+        CandidateKey {
+            foundation: candidate.foundation.clone(),
+            component_id: candidate.component_id,
+            transport: candidate.transport.clone(),
+            connection_address: candidate.connection_address,
+            port: candidate.port,
+        }
+    }
+}
+
+/// The outcome of inserting a candidate into an
+/// [`IceCandidateSet`](struct.IceCandidateSet.html).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum InsertOutcome {
+    /// The candidate was new and has been inserted.
+    Inserted,
+    /// An identical candidate was already part of the set. Nothing changed.
+    Duplicate,
+    /// A candidate with the same key but with different values was already
+    /// part of the set. It has been removed and the new candidate has been
+    /// inserted, subject to redundancy elimination.
+    Conflict {
+        /// The previous definition of the candidate.
+        previous: IceCandidate,
+        /// The candidate with a lower priority that the new definition made
+        /// redundant. It has been removed.
+        eliminated: Option<IceCandidate>,
+        /// Whether the new definition has been inserted. It is not inserted
+        /// if it is redundant to a candidate with the same or a higher
+        /// priority.
+        inserted: bool,
+    },
+    /// The candidate is redundant to a candidate with the same or a higher
+    /// priority that is already part of the set. It has not been inserted.
+    Redundant,
+    /// The candidate made a candidate with a lower priority redundant. That
+    /// candidate has been removed and the new candidate has been inserted.
+    Eliminated(IceCandidate),
+}
+
+/// A set of ICE candidates.
+///
+/// Candidates are identified by their foundation, component ID, transport,
+/// connection address and port (see [`CandidateKey`](struct.CandidateKey.html)).
+///
+/// When inserting, exact duplicates are ignored and conflicting re-definitions
+/// replace the previous candidate. Redundant candidates are eliminated as
+/// described in RFC5245 section 4.1.3: A candidate is redundant if its
+/// transport address and its base equal those of another candidate. Only the
+/// candidate with the higher priority is kept.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct IceCandidateSet {
+    candidates: HashMap<CandidateKey, IceCandidate>,
+}
+
+impl IceCandidateSet {
+    /// Create a new, empty candidate set.
+    pub fn new() -> Self {
+        IceCandidateSet { candidates: HashMap::new() }
+    }
+
+    /// Insert a candidate into the set.
+    ///
+    /// See [`InsertOutcome`](enum.InsertOutcome.html) for the possible results.
+    pub fn insert(&mut self, candidate: IceCandidate) -> InsertOutcome {
+        let key = CandidateKey::from(&candidate);
+
+        if self.candidates.get(&key) == Some(&candidate) {
+            return InsertOutcome::Duplicate;
+        }
+
+        let previous = self.candidates.remove(&key);
+
+        let redundant_key = self.candidates.iter()
+            .find(|&(_, c)| is_redundant(c, &candidate))
+            .map(|(k, _)| k.clone());
+        let eliminated = match redundant_key {
+            Some(redundant_key) => {
+                if self.candidates[&redundant_key].priority >= candidate.priority {
+                    return match previous {
+                        Some(previous) => InsertOutcome::Conflict { previous, eliminated: None, inserted: false },
+                        None => InsertOutcome::Redundant,
+                    };
+                }
+                self.candidates.remove(&redundant_key)
+            },
+            None => None,
+        };
+        self.candidates.insert(key, candidate);
+        match (previous, eliminated) {
+            (Some(previous), eliminated) => InsertOutcome::Conflict { previous, eliminated, inserted: true },
+            (None, Some(eliminated)) => InsertOutcome::Eliminated(eliminated),
+            (None, None) => InsertOutcome::Inserted,
+        }
+    }
+
+    /// Return the candidate with the specified key.
+    pub fn get(&self, key: &CandidateKey) -> Option<&IceCandidate> {
+        self.candidates.get(key)
+    }
+
+    /// Return whether a candidate with the specified key is part of the set.
+    pub fn contains_key(&self, key: &CandidateKey) -> bool {
+        self.candidates.contains_key(key)
+    }
+
+    /// Remove and return the candidate with the specified key.
+    pub fn remove(&mut self, key: &CandidateKey) -> Option<IceCandidate> {
+        self.candidates.remove(key)
+    }
+
+    /// Return the number of candidates in the set.
+    pub fn len(&self) -> usize {
+        self.candidates.len()
+    }
+
+    /// Return whether the set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.candidates.is_empty()
+    }
+
+    /// Remove all candidates from the set.
+    pub fn clear(&mut self) {
+        self.candidates.clear()
+    }
+
+    /// Iterate over the candidates, ordered by descending priority.
+    pub fn iter<'a>(&'a self) -> Iter<'a> {
+        let mut candidates: Vec<&IceCandidate> = self.candidates.values().collect();
+        candidates.sort_by(|a, b| priority_order(a, b));
+        Iter { inner: candidates.into_iter() }
+    }
+
+    /// Return the candidates as a vector, ordered by descending priority.
+    pub fn into_vec(self) -> Vec<IceCandidate> {
+        let mut candidates: Vec<IceCandidate> = self.candidates.into_values().collect();
+        candidates.sort_by(priority_order);
+        candidates
+    }
+
+    /// Iterate over the candidates in arbitrary order.
+    pub fn values<'a>(&'a self) -> hash_map::Values<'a, CandidateKey, IceCandidate> {
+        self.candidates.values()
+    }
+}
+
+impl Extend<IceCandidate> for IceCandidateSet {
+    fn extend<T: IntoIterator<Item=IceCandidate>>(&mut self, iter: T) {
+        for candidate in iter {
+            self.insert(candidate);
+        }
+    }
+}
+
+impl ::std::iter::FromIterator<IceCandidate> for IceCandidateSet {
+    fn from_iter<T: IntoIterator<Item=IceCandidate>>(iter: T) -> Self {
+        let mut set = IceCandidateSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<'a> IntoIterator for &'a IceCandidateSet {
+    type Item = &'a IceCandidate;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+/// An iterator over the candidates of an
+/// [`IceCandidateSet`](struct.IceCandidateSet.html), ordered by descending
+/// priority.
+#[derive(Debug)]
+pub struct Iter<'a> {
+    inner: vec::IntoIter<&'a IceCandidate>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a IceCandidate;
+
+    fn next(&mut self) -> Option<&'a IceCandidate> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+/// Return whether two candidates are redundant according to RFC5245 section
+/// 4.1.3 (same transport address and same base).
+fn is_redundant(a: &IceCandidate, b: &IceCandidate) -> bool {
+    if a.component_id != b.component_id
+        || a.transport != b.transport
+        || a.connection_address != b.connection_address
+        || a.port != b.port {
+        return false;
+    }
+    match (a.base(), b.base()) {
+        (Some(base_a), Some(base_b)) => base_a == base_b,
+        _ => false,
+    }
+}
+
+/// Order candidates by descending priority. Ties are broken by component ID,
+/// foundation, connection address and port to get a deterministic order.
+fn priority_order(a: &IceCandidate, b: &IceCandidate) -> ::std::cmp::Ordering {
+    b.priority.cmp(&a.priority)
+        .then_with(|| a.component_id.cmp(&b.component_id))
+        .then_with(|| a.foundation.cmp(&b.foundation))
+        .then_with(|| a.connection_address.cmp(&b.connection_address))
+        .then_with(|| a.port.cmp(&b.port))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use ::parse;
+
+    use super::*;
+
+    fn candidate(sdp: &str) -> IceCandidate {
+        parse(sdp.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut set = IceCandidateSet::new();
+        assert!(set.is_empty());
+        let host = candidate("candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host");
+        assert_eq!(set.insert(host.clone()), InsertOutcome::Inserted);
+        assert_eq!(set.len(), 1);
+        assert_eq!(set.get(&CandidateKey::from(&host)), Some(&host));
+    }
+
+    #[test]
+    fn test_insert_duplicate() {
+        let mut set = IceCandidateSet::new();
+        let host = candidate("candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host generation 0");
+        assert_eq!(set.insert(host.clone()), InsertOutcome::Inserted);
+        assert_eq!(set.insert(host), InsertOutcome::Duplicate);
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn test_insert_conflict() {
+        let mut set = IceCandidateSet::new();
+        let host1 = candidate("candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host generation 0");
+        let host2 = candidate("candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host generation 1");
+        assert_eq!(set.insert(host1.clone()), InsertOutcome::Inserted);
+        assert_eq!(set.insert(host2.clone()), InsertOutcome::Conflict { previous: host1, eliminated: None, inserted: true });
+        assert_eq!(set.len(), 1);
+        assert_eq!(set.get(&CandidateKey::from(&host2)), Some(&host2));
+    }
+
+    #[test]
+    fn test_insert_conflict_redundant() {
+        let host = candidate("candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host");
+        let srflx1 = candidate("candidate:2 1 udp 1686052607 10.0.0.17 46154 typ srflx raddr 10.0.0.18 rport 46154");
+        let srflx2 = candidate("candidate:2 1 udp 1686052607 10.0.0.17 46154 typ srflx raddr 10.0.0.17 rport 46154");
+
+        // The new definition is redundant to a candidate with higher priority
+        let mut set = IceCandidateSet::new();
+        assert_eq!(set.insert(host.clone()), InsertOutcome::Inserted);
+        assert_eq!(set.insert(srflx1.clone()), InsertOutcome::Inserted);
+        assert_eq!(set.insert(srflx2.clone()), InsertOutcome::Conflict {
+            previous: srflx1.clone(),
+            eliminated: None,
+            inserted: false,
+        });
+        assert_eq!(set.len(), 1);
+        assert!(set.contains_key(&CandidateKey::from(&host)));
+        assert!(!set.contains_key(&CandidateKey::from(&srflx2)));
+
+        // The new definition makes a candidate with lower priority redundant
+        let low = candidate("candidate:3 1 udp 1 10.0.0.17 46154 typ srflx raddr 10.0.0.17 rport 46154");
+        let mut set = IceCandidateSet::new();
+        assert_eq!(set.insert(low.clone()), InsertOutcome::Inserted);
+        assert_eq!(set.insert(srflx1.clone()), InsertOutcome::Inserted);
+        assert_eq!(set.insert(srflx2.clone()), InsertOutcome::Conflict {
+            previous: srflx1,
+            eliminated: Some(low),
+            inserted: true,
+        });
+        assert_eq!(set.len(), 1);
+        assert_eq!(set.get(&CandidateKey::from(&srflx2)), Some(&srflx2));
+    }
+
+    #[test]
+    fn test_insert_redundant() {
+        let mut set = IceCandidateSet::new();
+        let host = candidate("candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host");
+        let srflx = candidate("candidate:2 1 udp 1686052607 10.0.0.17 46154 typ srflx raddr 10.0.0.17 rport 46154");
+        assert_eq!(set.insert(host.clone()), InsertOutcome::Inserted);
+        assert_eq!(set.insert(srflx.clone()), InsertOutcome::Redundant);
+        assert_eq!(set.len(), 1);
+
+        let mut set = IceCandidateSet::new();
+        assert_eq!(set.insert(srflx.clone()), InsertOutcome::Inserted);
+        assert_eq!(set.insert(host.clone()), InsertOutcome::Eliminated(srflx));
+        assert_eq!(set.len(), 1);
+        assert!(set.contains_key(&CandidateKey::from(&host)));
+    }
+
+    #[test]
+    fn test_not_redundant_different_base() {
+        let mut set = IceCandidateSet::new();
+        let srflx1 = candidate("candidate:1 1 udp 1686052607 1.2.3.4 46154 typ srflx raddr 10.0.0.17 rport 46154");
+        let srflx2 = candidate("candidate:2 1 udp 1686052607 1.2.3.4 46154 typ srflx raddr 10.0.0.18 rport 46154");
+        assert_eq!(set.insert(srflx1), InsertOutcome::Inserted);
+        assert_eq!(set.insert(srflx2), InsertOutcome::Inserted);
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn test_iter_priority_order() {
+        let set: IceCandidateSet = vec![
+            candidate("candidate:3 1 udp 41885439 5.148.189.205 63293 typ relay"),
+            candidate("candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host"),
+            candidate("candidate:2 1 udp 1686052607 1.2.3.4 46154 typ srflx raddr 10.0.0.17 rport 46154"),
+        ].into_iter().collect();
+        let foundations: Vec<&str> = set.iter().map(|c| c.foundation.as_str()).collect();
+        assert_eq!(foundations, vec!["1", "2", "3"]);
+        let foundations: Vec<String> = set.into_vec().into_iter().map(|c| c.foundation).collect();
+        assert_eq!(foundations, vec!["1", "2", "3"]);
+    }
+}
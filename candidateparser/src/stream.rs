@@ -0,0 +1,200 @@
+//! Incremental parsing of candidates arriving over a byte stream.
+use alloc::vec::Vec;
+use core::mem;
+
+use ::batch::{self, LineError, LineResult};
+use ::reader::{Field, ParseError};
+
+/// A push based parser for newline separated candidates.
+///
+/// Feed it arbitrary chunks of bytes using [`push`](#method.push). Complete
+/// lines are parsed as soon as their line terminator arrives, partial lines
+/// are buffered until the next chunk. Lines are handled like in
+/// [`parse_buffer`](fn.parse_buffer.html): They may be terminated by LF or
+/// CRLF, may be prefixed with `a=` and empty lines are skipped.
+///
+/// To limit the memory used for buffering, lines may not be longer than
+/// [`DEFAULT_MAX_LINE_LEN`](#associatedconstant.DEFAULT_MAX_LINE_LEN) bytes
+/// (see [`with_max_line_len`](#method.with_max_line_len)). For longer lines,
+/// an error with the field [`LineLength`](enum.Field.html#variant.LineLength)
+/// is returned as soon as the limit is exceeded, and the rest of the line is
+/// discarded.
+///
+/// ## Example
+///
+/// ```rust
+/// use candidateparser::StreamParser;
+///
+/// let mut parser = StreamParser::new();
+/// assert!(parser.push(b"candidate:1 1 udp 2130706431 10.0.0").is_empty());
+/// let results = parser.push(b".17 46154 typ host\r\ncandidate:2");
+/// assert_eq!(results.len(), 1);
+/// assert_eq!(parser.buffered(), b"candidate:2");
+/// ```
+#[derive(Debug, Clone)]
+pub struct StreamParser {
+    buf: Vec<u8>,
+    line: usize,
+    max_line_len: usize,
+    /// Whether the current line exceeded the maximum length and is being
+    /// discarded.
+    discarding: bool,
+}
+
+impl Default for StreamParser {
+    fn default() -> Self {
+        StreamParser::with_max_line_len(StreamParser::DEFAULT_MAX_LINE_LEN)
+    }
+}
+
+impl StreamParser {
+    /// The default maximum line length in bytes.
+    pub const DEFAULT_MAX_LINE_LEN: usize = 4096;
+
+    /// Create a new parser with an empty buffer.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Create a new parser with an empty buffer that accepts lines of at
+    /// most `max_line_len` bytes (including a carriage return, excluding
+    /// the line feed).
+    pub fn with_max_line_len(max_line_len: usize) -> Self {
+        StreamParser {
+            buf: vec![],
+            line: 0,
+            max_line_len,
+            discarding: false,
+        }
+    }
+
+    /// Append a chunk of bytes and return the results of all lines that
+    /// were completed by it.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<LineResult> {
+        let mut results = vec![];
+        let mut rest = chunk;
+        loop {
+            let pos = rest.iter().position(|&c| c == b'\n');
+            let line = &rest[..pos.unwrap_or(rest.len())];
+            if self.discarding {
+                // The error has already been returned
+            } else if self.buf.len() + line.len() > self.max_line_len {
+                self.buf.clear();
+                self.discarding = true;
+                results.push(Err(LineError {
+                    line: self.line + 1,
+                    error: ParseError { field: Field::LineLength, offset: self.max_line_len },
+                }));
+            } else if pos.is_none() {
+                self.buf.extend_from_slice(line);
+            } else if self.buf.is_empty() {
+                results.extend(batch::parse_line(self.line + 1, line));
+            } else {
+                self.buf.extend_from_slice(line);
+                let line = mem::take(&mut self.buf);
+                results.extend(batch::parse_line(self.line + 1, &line));
+            }
+            match pos {
+                Some(pos) => {
+                    self.line += 1;
+                    self.discarding = false;
+                    rest = &rest[pos + 1..];
+                },
+                None => return results,
+            }
+        }
+    }
+
+    /// Return the bytes of the incomplete line that is currently buffered.
+    pub fn buffered(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// Finish parsing and return the result of the last line, if it was
+    /// not terminated by a newline. If the last line exceeded the maximum
+    /// length, its error has already been returned by `push`.
+    pub fn finish(self) -> Option<LineResult> {
+        if self.discarding {
+            return None;
+        }
+        batch::parse_line(self.line + 1, &self.buf)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use ::batch::LineError;
+    use ::reader::{Field, ParseError};
+
+    use super::*;
+
+    #[test]
+    fn test_push_split_lines() {
+        let sdp = b"candidate:1 1 udp 2130706431 10.0.0.17 46154 typ host\r\n\
+                    candidate:2 1 udp 1686052607 1.2.3.4 46154 typ srflx raddr 10.0.0.17 rport 46154\r\n";
+        for chunk_size in 1..sdp.len() {
+            let mut parser = StreamParser::new();
+            let mut results = vec![];
+            for chunk in sdp.chunks(chunk_size) {
+                results.extend(parser.push(chunk));
+            }
+            assert_eq!(results.len(), 2);
+            assert_eq!(results[0].as_ref().unwrap().0, 1);
+            assert_eq!(results[0].as_ref().unwrap().1.foundation, "1");
+            assert_eq!(results[1].as_ref().unwrap().0, 2);
+            assert_eq!(results[1].as_ref().unwrap().1.foundation, "2");
+            assert!(parser.buffered().is_empty());
+            assert!(parser.finish().is_none());
+        }
+    }
+
+    #[test]
+    fn test_push_errors() {
+        let mut parser = StreamParser::new();
+        let results = parser.push(b"\na=candidate:1 1 udp 1 1.2.3.4 5 typ\n\na=candidate:1 1 udp 1 1.2.3.4 5 typ host\n");
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0], Err(LineError { line: 2, error: ParseError { field: Field::CandidateType, offset: 32 } }));
+        assert_eq!(results[1].as_ref().unwrap().0, 4);
+    }
+
+    #[test]
+    fn test_finish() {
+        let mut parser = StreamParser::new();
+        assert!(parser.push(b"candidate:1 1 udp 1 1.2.3.4 5 typ host\ncandidate:2 1 udp").len() == 1);
+        assert_eq!(parser.buffered(), b"candidate:2 1 udp");
+        assert_eq!(parser.finish(), Some(Err(LineError { line: 2, error: ParseError { field: Field::Priority, offset: 17 } })));
+
+        let mut parser = StreamParser::new();
+        assert!(parser.push(b"candidate:2 1 udp 1 1.2.3.4 5 typ host").is_empty());
+        assert_eq!(parser.finish().unwrap().unwrap().1.foundation, "2");
+    }
+
+    #[test]
+    fn test_max_line_len() {
+        let line = b"candidate:1 1 udp 1 1.2.3.4 5 typ host";
+        let mut parser = StreamParser::with_max_line_len(line.len());
+        let results = parser.push(line);
+        assert!(results.is_empty());
+        let results = parser.push(b"\ncandidate:2 1 udp 1 1.2.3.4 5 typ host generation 0");
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].as_ref().unwrap().0, 1);
+        assert_eq!(results[1], Err(LineError { line: 2, error: ParseError { field: Field::LineLength, offset: line.len() } }));
+        assert!(parser.buffered().is_empty());
+
+        // The rest of the line is discarded, without reporting it again
+        for _ in 0..100 {
+            assert!(parser.push(b" foo bar").is_empty());
+        }
+        assert!(parser.buffered().is_empty());
+        let results = parser.push(b" baz\ncandidate:3 1 udp 1 1.2.3.4 5 typ host\n");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].as_ref().unwrap().0, 3);
+        assert!(parser.finish().is_none());
+
+        let mut parser = StreamParser::with_max_line_len(10);
+        assert_eq!(parser.push(b"candidate:1 1").len(), 1);
+        assert!(parser.finish().is_none());
+        assert_eq!(StreamParser::new().max_line_len, StreamParser::DEFAULT_MAX_LINE_LEN);
+    }
+}
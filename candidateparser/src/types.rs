@@ -1,8 +1,31 @@
-use std::convert::Into;
+use core::convert::Into;
+use core::fmt;
+use core::net::{IpAddr, SocketAddr};
+use core::str;
+
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap;
+use alloc::ffi::CString;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
 use std::collections::HashMap;
-use std::ffi::CString;
-use std::fmt;
-use std::net::IpAddr;
+
+use ::reader::ParseError;
+
+/// The map containing the candidate extensions.
+///
+/// With the `std` feature (enabled by default) this is a `HashMap`, otherwise
+/// a `BTreeMap`.
+#[cfg(feature = "std")]
+pub type ExtensionMap = HashMap<Vec<u8>, Vec<u8>>;
+
+/// The map containing the candidate extensions.
+///
+/// With the `std` feature (enabled by default) this is a `HashMap`, otherwise
+/// a `BTreeMap`.
+#[cfg(not(feature = "std"))]
+pub type ExtensionMap = BTreeMap<Vec<u8>, Vec<u8>>;
 
 /// The ICE candidate struct. Contains all data from the SDP.
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -16,7 +39,158 @@ pub struct IceCandidate {
     pub candidate_type: CandidateType,
     pub rel_addr: Option<IpAddr>,
     pub rel_port: Option<u16>,
-    pub extensions: Option<HashMap<Vec<u8>, Vec<u8>>>,
+    pub extensions: Option<ExtensionMap>,
+}
+
+impl IceCandidate {
+    /// Serialize the candidate to an SDP bytestring.
+    ///
+    /// This is the inverse of [`parse`](fn.parse.html). Extensions are
+    /// written in lexicographical order of their names. Note that extensions
+    /// named `raddr` or `rport` cannot be distinguished from the rel-addr and
+    /// rel-port fields when parsing the result again.
+    pub fn to_sdp(&self) -> Vec<u8> {
+        let mut sdp = format!(
+            "candidate:{} {} {} {} {} {} typ {}",
+            self.foundation, self.component_id, self.transport, self.priority,
+            self.connection_address, self.port, self.candidate_type
+        ).into_bytes();
+        if let Some(addr) = self.rel_addr {
+            sdp.extend_from_slice(format!(" raddr {}", addr).as_bytes());
+        }
+        if let Some(port) = self.rel_port {
+            sdp.extend_from_slice(format!(" rport {}", port).as_bytes());
+        }
+        if let Some(ref extensions) = self.extensions {
+            let mut pairs: Vec<(&Vec<u8>, &Vec<u8>)> = extensions.iter().collect();
+            pairs.sort();
+            for (key, val) in pairs {
+                sdp.push(b' ');
+                sdp.extend_from_slice(key);
+                sdp.push(b' ');
+                sdp.extend_from_slice(val);
+            }
+        }
+        sdp
+    }
+
+    /// Check that every field of the candidate is valid, so that the result
+    /// of [`to_sdp`](#method.to_sdp) is parsed to the same candidate.
+    ///
+    /// This is useful for candidates built field by field. On failure, the
+    /// error contains the first invalid field and the offset `0`.
+    pub fn validate(&self) -> Result<(), ParseError> {
+        ::reader::validate(self)
+    }
+
+    /// Return the base of this candidate.
+    ///
+    /// According to RFC5245 section 2.1, host and relayed candidates are
+    /// their own base, while the base of a server or peer reflexive
+    /// candidate is the transport address it was derived from (the related
+    /// address). If the related address of a reflexive candidate is unknown,
+    /// or if the candidate type is unknown, `None` is returned.
+    pub fn base(&self) -> Option<SocketAddr> {
+        match self.candidate_type {
+            CandidateType::Host | CandidateType::Relay => {
+                Some(SocketAddr::new(self.connection_address, self.port))
+            },
+            CandidateType::Srflx | CandidateType::Prflx => {
+                match (self.rel_addr, self.rel_port) {
+                    (Some(addr), Some(port)) => Some(SocketAddr::new(addr, port)),
+                    _ => None,
+                }
+            },
+            CandidateType::Token(_) => None,
+        }
+    }
+
+    /// Create a peer reflexive candidate from the mapped address learned
+    /// through a connectivity check.
+    ///
+    /// This implements RFC5245 section 7.1.3.2.1: The new candidate uses the
+    /// XOR-MAPPED-ADDRESS of the binding response as its transport address,
+    /// the `priority` value of the PRIORITY attribute sent in the binding
+    /// request, and shares the component and transport of the `base`
+    /// candidate. The base transport address is stored as related address.
+    ///
+    /// The foundation is derived from the candidate type, the base IP address
+    /// and the transport, so that two peer reflexive candidates learned from
+    /// the same base share the same foundation (RFC5245 section 4.1.1.3).
+    ///
+    /// If the base of the `base` candidate cannot be determined (see
+    /// [`base`](#method.base)), `None` is returned.
+    pub fn peer_reflexive(mapped_address: SocketAddr, base: &IceCandidate, priority: u32) -> Option<IceCandidate> {
+        let base_address = base.base()?;
+        Some(IceCandidate {
+            foundation: compute_foundation(&CandidateType::Prflx, &base_address.ip(), &base.transport),
+            component_id: base.component_id,
+            transport: base.transport.clone(),
+            priority: u64::from(priority),
+            connection_address: mapped_address.ip(),
+            port: mapped_address.port(),
+            candidate_type: CandidateType::Prflx,
+            rel_addr: Some(base_address.ip()),
+            rel_port: Some(base_address.port()),
+            extensions: None,
+        })
+    }
+
+    /// Return the value of the extension with the specified name.
+    pub fn extension(&self, name: &[u8]) -> Option<&[u8]> {
+        self.extensions.as_ref()
+            .and_then(|e| e.get(name))
+            .map(|v| v.as_slice())
+    }
+
+    /// Return the value of the `ufrag` extension.
+    pub fn ufrag(&self) -> Option<&[u8]> {
+        self.extension(b"ufrag")
+    }
+
+    /// Return the value of the `generation` extension.
+    ///
+    /// If the extension is missing or if it is not a valid number, `None` is
+    /// returned.
+    pub fn generation(&self) -> Option<u32> {
+        self.numeric_extension(b"generation")
+    }
+
+    /// Return the value of the `network-id` extension.
+    ///
+    /// If the extension is missing or if it is not a valid number, `None` is
+    /// returned.
+    pub fn network_id(&self) -> Option<u32> {
+        self.numeric_extension(b"network-id")
+    }
+
+    /// Return the value of the `network-cost` extension.
+    ///
+    /// If the extension is missing or if it is not a valid number, `None` is
+    /// returned.
+    pub fn network_cost(&self) -> Option<u32> {
+        self.numeric_extension(b"network-cost")
+    }
+
+    /// Return the value of the extension with the specified name as number.
+    fn numeric_extension(&self, name: &[u8]) -> Option<u32> {
+        self.extension(name)
+            .and_then(|v| str::from_utf8(v).ok())
+            .and_then(|v| v.parse().ok())
+    }
+}
+
+/// Compute a foundation from the candidate type, the base IP address and the
+/// transport.
+///
+/// The foundation is the decimal representation of the 32 bit FNV-1a hash of
+/// these values. It is deterministic and consists only of ice-chars.
+fn compute_foundation(candidate_type: &CandidateType, base_ip: &IpAddr, transport: &Transport) -> String {
+    let input = format!("{}{}{}", candidate_type, base_ip, transport.to_string().to_lowercase());
+    let hash = input.bytes().fold(0x811c_9dc5u32, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    });
+    hash.to_string()
 }
 
 /// The transport type. In almost all cases this will be `Transport::Udp`.
@@ -105,4 +279,112 @@ mod tests {
         assert_eq!(relay, CString::new("relay").unwrap());
         assert_eq!(token, CString::new("Yolo").unwrap());
     }
+
+    fn host_candidate() -> IceCandidate {
+        IceCandidate {
+            foundation: "1".into(),
+            component_id: 2,
+            transport: Transport::Udp,
+            priority: 2130706431,
+            connection_address: "10.0.0.17".parse().unwrap(),
+            port: 46154,
+            candidate_type: CandidateType::Host,
+            rel_addr: None,
+            rel_port: None,
+            extensions: None,
+        }
+    }
+
+    #[test]
+    fn test_to_sdp() {
+        let mut candidate = host_candidate();
+        assert_eq!(candidate.to_sdp(), b"candidate:1 2 udp 2130706431 10.0.0.17 46154 typ host".to_vec());
+
+        candidate.candidate_type = CandidateType::Srflx;
+        candidate.rel_addr = Some("::1".parse().unwrap());
+        candidate.rel_port = Some(1337);
+        let mut extensions = ExtensionMap::new();
+        extensions.insert(b"ufrag".to_vec(), b"EEtu".to_vec());
+        extensions.insert(b"generation".to_vec(), b"0".to_vec());
+        candidate.extensions = Some(extensions);
+        assert_eq!(candidate.to_sdp(), b"candidate:1 2 udp 2130706431 10.0.0.17 46154 typ srflx raddr ::1 rport 1337 generation 0 ufrag EEtu".to_vec());
+    }
+
+    #[test]
+    fn test_base() {
+        let host = host_candidate();
+        assert_eq!(host.base(), Some("10.0.0.17:46154".parse().unwrap()));
+
+        let mut srflx = host_candidate();
+        srflx.candidate_type = CandidateType::Srflx;
+        srflx.connection_address = "1.2.3.4".parse().unwrap();
+        assert_eq!(srflx.base(), None);
+        srflx.rel_addr = Some("10.0.0.17".parse().unwrap());
+        srflx.rel_port = Some(46154);
+        assert_eq!(srflx.base(), Some("10.0.0.17:46154".parse().unwrap()));
+
+        let mut token = host_candidate();
+        token.candidate_type = CandidateType::Token("foo".into());
+        assert_eq!(token.base(), None);
+    }
+
+    #[test]
+    fn test_peer_reflexive() {
+        let host = host_candidate();
+        let prflx = IceCandidate::peer_reflexive("1.2.3.4:1337".parse().unwrap(), &host, 1845501695).unwrap();
+        assert_eq!(prflx.component_id, 2);
+        assert_eq!(prflx.transport, Transport::Udp);
+        assert_eq!(prflx.priority, 1845501695);
+        assert_eq!(prflx.connection_address, "1.2.3.4".parse::<IpAddr>().unwrap());
+        assert_eq!(prflx.port, 1337);
+        assert_eq!(prflx.candidate_type, CandidateType::Prflx);
+        assert_eq!(prflx.rel_addr, Some("10.0.0.17".parse().unwrap()));
+        assert_eq!(prflx.rel_port, Some(46154));
+        assert_eq!(prflx.extensions, None);
+        assert!(!prflx.foundation.is_empty() && prflx.foundation.len() <= 32);
+        assert_ne!(prflx.foundation, host.foundation);
+
+        // Same base IP and transport, different mapped address: Same foundation
+        let mut other_base = host_candidate();
+        other_base.port = 5000;
+        let prflx2 = IceCandidate::peer_reflexive("1.2.3.4:1338".parse().unwrap(), &other_base, 1845501695).unwrap();
+        assert_eq!(prflx.foundation, prflx2.foundation);
+
+        // Different base IP: Different foundation
+        let mut other_ip = host_candidate();
+        other_ip.connection_address = "10.0.0.18".parse().unwrap();
+        let prflx3 = IceCandidate::peer_reflexive("1.2.3.4:1337".parse().unwrap(), &other_ip, 1845501695).unwrap();
+        assert_ne!(prflx.foundation, prflx3.foundation);
+    }
+
+    #[test]
+    fn test_extension_accessors() {
+        let mut candidate = host_candidate();
+        assert_eq!(candidate.extension(b"ufrag"), None);
+        assert_eq!(candidate.ufrag(), None);
+        assert_eq!(candidate.generation(), None);
+        assert_eq!(candidate.network_id(), None);
+        assert_eq!(candidate.network_cost(), None);
+
+        let mut extensions = ExtensionMap::new();
+        extensions.insert(b"ufrag".to_vec(), b"EEtu".to_vec());
+        extensions.insert(b"generation".to_vec(), b"2".to_vec());
+        extensions.insert(b"network-id".to_vec(), b"3".to_vec());
+        extensions.insert(b"network-cost".to_vec(), b"10".to_vec());
+        candidate.extensions = Some(extensions);
+        assert_eq!(candidate.ufrag(), Some(&b"EEtu"[..]));
+        assert_eq!(candidate.generation(), Some(2));
+        assert_eq!(candidate.network_id(), Some(3));
+        assert_eq!(candidate.network_cost(), Some(10));
+
+        candidate.extensions.as_mut().unwrap().insert(b"generation".to_vec(), b"x".to_vec());
+        assert_eq!(candidate.generation(), None);
+    }
+
+    #[test]
+    fn test_peer_reflexive_unknown_base() {
+        let mut srflx = host_candidate();
+        srflx.candidate_type = CandidateType::Srflx;
+        assert_eq!(IceCandidate::peer_reflexive("1.2.3.4:1337".parse().unwrap(), &srflx, 1), None);
+    }
 }
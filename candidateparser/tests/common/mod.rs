@@ -0,0 +1,86 @@
+//! Generators for valid ICE candidates.
+use std::net::IpAddr;
+
+use candidateparser::{IceCandidate, CandidateType, Transport, ExtensionMap};
+use proptest::prelude::*;
+
+/// 1 to 32 RFC5245 ice-chars.
+fn foundation() -> impl Strategy<Value=String> {
+    "[a-zA-Z0-9+/]{1,32}"
+}
+
+fn transport() -> impl Strategy<Value=Transport> {
+    prop_oneof![
+        Just(Transport::Udp),
+        "[a-zA-Z]{1,8}"
+            .prop_filter("Must not be UDP", |t| !t.eq_ignore_ascii_case("udp"))
+            .prop_map(Transport::Extension),
+    ]
+}
+
+fn candidate_type() -> impl Strategy<Value=CandidateType> {
+    prop_oneof![
+        Just(CandidateType::Host),
+        Just(CandidateType::Srflx),
+        Just(CandidateType::Prflx),
+        Just(CandidateType::Relay),
+        "[a-zA-Z]{1,8}"
+            .prop_filter("Must not be a known type", |t| !["host", "srflx", "prflx", "relay"].contains(&t.as_str()))
+            .prop_map(CandidateType::Token),
+    ]
+}
+
+fn ip_addr() -> impl Strategy<Value=IpAddr> {
+    prop_oneof![
+        any::<[u8; 4]>().prop_map(IpAddr::from),
+        any::<[u8; 16]>().prop_map(IpAddr::from),
+    ]
+}
+
+/// Extension names and values are byte-strings without space, NUL, CR and LF.
+///
+/// Tabs are excluded as well, since the parser treats them as separators.
+fn byte_string() -> impl Strategy<Value=Vec<u8>> {
+    proptest::collection::vec(
+        any::<u8>().prop_filter("Invalid byte-string char", |&b| ![0x00, 0x09, 0x0a, 0x0d, 0x20].contains(&b)),
+        1..16,
+    )
+}
+
+fn extensions() -> impl Strategy<Value=Option<ExtensionMap>> {
+    proptest::option::of(
+        proptest::collection::hash_map(
+            byte_string().prop_filter("Must not be raddr or rport", |k| k != b"raddr" && k != b"rport"),
+            byte_string(),
+            1..5,
+        ).prop_map(|map| map.into_iter().collect())
+    )
+}
+
+prop_compose! {
+    pub fn ice_candidate()(
+        foundation in foundation(),
+        component_id in 0u32..100_000,
+        transport in transport(),
+        priority in 0u64..10_000_000_000,
+        connection_address in ip_addr(),
+        port in any::<u16>(),
+        candidate_type in candidate_type(),
+        rel_addr in proptest::option::of(ip_addr()),
+        rel_port in proptest::option::of(any::<u16>()),
+        extensions in extensions(),
+    ) -> IceCandidate {
+        IceCandidate {
+            foundation,
+            component_id,
+            transport,
+            priority,
+            connection_address,
+            port,
+            candidate_type,
+            rel_addr,
+            rel_port,
+            extensions,
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! Property based tests ensuring that the hand-written parser behaves exactly
+//! like the nom parsers.
+#![cfg(feature = "parsers")]
+extern crate candidateparser;
+extern crate proptest;
+
+mod common;
+
+use candidateparser::{parse, parsers, IceCandidate};
+use common::ice_candidate;
+use proptest::prelude::*;
+
+/// Parse the candidate using the nom parsers, the same way `parse` did
+/// before the hand-written parser was introduced.
+fn parse_nom(sdp: &[u8]) -> Option<IceCandidate> {
+    let result = parsers::ice_candidate(sdp);
+    if !result.is_done() {
+        return None;
+    }
+    match result.unwrap() {
+        (&[], candidate) => Some(candidate),
+        _ => None,
+    }
+}
+
+/// Bytes that are significant for the grammar.
+fn interesting_byte() -> impl Strategy<Value=u8> {
+    prop_oneof![
+        any::<u8>(),
+        prop::sample::select(vec![b' ', b'\t', b'\r', b'\n', 0, b':', b'.', b'+', b'/', b'-', b'0', b'9', b'a', b'z']),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn equivalent_valid(candidate in ice_candidate()) {
+        let sdp = candidate.to_sdp();
+        prop_assert_eq!(parse(&sdp), parse_nom(&sdp));
+    }
+
+    #[test]
+    fn equivalent_arbitrary(input in proptest::collection::vec(any::<u8>(), 0..256)) {
+        prop_assert_eq!(parse(&input), parse_nom(&input));
+    }
+
+    #[test]
+    fn equivalent_truncated(candidate in ice_candidate(), len in any::<prop::sample::Index>()) {
+        let sdp = candidate.to_sdp();
+        let truncated = &sdp[..len.index(sdp.len() + 1)];
+        prop_assert_eq!(parse(truncated), parse_nom(truncated));
+    }
+
+    #[test]
+    fn equivalent_mutated(
+        candidate in ice_candidate(),
+        mutations in proptest::collection::vec((any::<prop::sample::Index>(), interesting_byte(), 0u8..3), 1..4),
+    ) {
+        let mut sdp = candidate.to_sdp();
+        for (pos, byte, kind) in mutations {
+            let pos = pos.index(sdp.len());
+            match kind {
+                0 => sdp[pos] = byte,
+                1 => sdp.insert(pos, byte),
+                _ => { sdp.remove(pos); },
+            }
+            if sdp.is_empty() {
+                break;
+            }
+        }
+        prop_assert_eq!(parse(&sdp), parse_nom(&sdp));
+    }
+
+    #[test]
+    fn no_panic_sub_parsers(input in proptest::collection::vec(any::<u8>(), 0..64)) {
+        let _ = parsers::foundation(&input);
+        let _ = parsers::component_id(&input);
+        let _ = parsers::transport(&input);
+        let _ = parsers::priority(&input);
+        let _ = parsers::cand_type(&input);
+        let _ = parsers::ip_addr(&input);
+        let _ = parsers::port(&input);
+        let _ = parsers::rel_addr(&input);
+        let _ = parsers::rel_port(&input);
+        let _ = parsers::extension(&input);
+    }
+}
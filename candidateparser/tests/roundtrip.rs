@@ -0,0 +1,48 @@
+//! Property based tests for the candidate parser.
+//!
+//! Valid candidates are generated, serialized and parsed again. Additionally,
+//! the parser is fed with arbitrary and mutated input to ensure that it never
+//! panics.
+extern crate candidateparser;
+extern crate proptest;
+
+mod common;
+
+use candidateparser::parse;
+use common::ice_candidate;
+use proptest::prelude::*;
+
+proptest! {
+    #[test]
+    fn roundtrip(candidate in ice_candidate()) {
+        let sdp = candidate.to_sdp();
+        prop_assert_eq!(parse(&sdp), Some(candidate));
+    }
+
+    #[test]
+    fn roundtrip_serialized(candidate in ice_candidate()) {
+        let sdp = candidate.to_sdp();
+        let reparsed = parse(&sdp).unwrap();
+        prop_assert_eq!(reparsed.to_sdp(), sdp);
+    }
+
+    #[test]
+    fn no_panic_arbitrary(input in proptest::collection::vec(any::<u8>(), 0..256)) {
+        parse(&input);
+    }
+
+    #[test]
+    fn no_panic_truncated(candidate in ice_candidate(), len in any::<prop::sample::Index>()) {
+        let sdp = candidate.to_sdp();
+        let truncated = &sdp[..len.index(sdp.len() + 1)];
+        parse(truncated);
+    }
+
+    #[test]
+    fn no_panic_mutated(candidate in ice_candidate(), pos in any::<prop::sample::Index>(), byte in any::<u8>()) {
+        let mut sdp = candidate.to_sdp();
+        let pos = pos.index(sdp.len());
+        sdp[pos] = byte;
+        parse(&sdp);
+    }
+}